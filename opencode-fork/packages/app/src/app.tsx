@@ -43,7 +43,13 @@ function UiI18nBridge(props: ParentProps) {
 
 declare global {
   interface Window {
-    __OPENCODE__?: { updaterEnabled?: boolean; serverPassword?: string; deepLinks?: string[] }
+    __OPENCODE__?: {
+      updaterEnabled?: boolean
+      serverUsername?: string
+      serverPassword?: string
+      serverAuthorization?: string
+      deepLinks?: string[]
+    }
   }
 }
 
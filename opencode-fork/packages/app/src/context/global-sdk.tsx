@@ -14,10 +14,13 @@ export const { use: useGlobalSDK, provider: GlobalSDKProvider } = createSimpleCo
 
     const auth = (() => {
       if (typeof window === "undefined") return
+      const authorization = window.__OPENCODE__?.serverAuthorization
+      if (authorization) return { Authorization: authorization }
       const password = window.__OPENCODE__?.serverPassword
       if (!password) return
+      const username = window.__OPENCODE__?.serverUsername ?? "opencode"
       return {
-        Authorization: `Basic ${btoa(`opencode:${password}`)}`,
+        Authorization: `Basic ${btoa(`${username}:${password}`)}`,
       }
     })()
 
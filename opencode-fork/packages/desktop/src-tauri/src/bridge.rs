@@ -0,0 +1,374 @@
+//! Loopback bridge that lets the webview reach servers with custom TLS settings or bearer
+//! tokens.
+//!
+//! `tauri-plugin-http` builds its own client for every request, so there's no way to hand it
+//! extra roots, a pinned certificate or a client identity. Browsers also can't set headers on
+//! WebSockets, so the terminal can only authenticate with credentials in its URL. When the
+//! active profile needs any of those, the webview talks plain HTTP to this bridge on 127.0.0.1
+//! instead, and each request (including WebSocket upgrades) is forwarded upstream through
+//! [`tls::client_builder`]. The bridge has its own random password, which is swapped for the
+//! profile's credentials upstream.
+//!
+//! The same bridge also fronts a sidecar listening on a Unix socket (see
+//! [`transport`](crate::transport)). It then shares the sidecar's password, and requests are
+//! passed through to the socket unchanged.
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::{convert::Infallible, fmt::Display, sync::Arc};
+
+use base64::Engine;
+use futures::TryStreamExt;
+use http_body_util::{BodyExt, Empty, Full, StreamBody, combinators::UnsyncBoxBody};
+use hyper::{
+    Request, Response, StatusCode,
+    body::{Bytes, Frame, Incoming},
+    header::{self, HeaderMap, HeaderValue},
+    server::conn::http1,
+    service::service_fn,
+    upgrade::OnUpgrade,
+};
+use hyper_util::rt::TokioIo;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+};
+
+use crate::{
+    profiles::{ServerAuth, ServerProfile},
+    redact, server, tls,
+};
+
+type Body = UnsyncBoxBody<Bytes, std::io::Error>;
+
+/// Headers that describe a single connection and must not be forwarded.
+const HOP_BY_HOP: &[header::HeaderName] = &[
+    header::CONNECTION,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+pub struct Bridge {
+    pub url: String,
+    pub password: String,
+}
+
+enum Target {
+    Http {
+        base: reqwest::Url,
+        client: reqwest::Client,
+        auth: Option<ServerAuth>,
+    },
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+struct Upstream {
+    target: Target,
+    expected_authorization: HeaderValue,
+}
+
+fn authorization(password: &str) -> Result<HeaderValue, String> {
+    let credentials = base64::engine::general_purpose::STANDARD.encode(format!(
+        "{}:{}",
+        server::SIDECAR_USERNAME,
+        password
+    ));
+    HeaderValue::from_str(&format!("Basic {credentials}"))
+        .map_err(|e| format!("Failed to build bridge credentials: {}", e))
+}
+
+pub async fn start(profile: &ServerProfile) -> Result<Bridge, String> {
+    let base = reqwest::Url::parse(&profile.url)
+        .map_err(|e| format!("Invalid server URL '{}': {}", profile.url, e))?;
+    let client = tls::client_builder(profile.tls.as_ref())?
+        .build()
+        .map_err(|e| format!("Failed to build client: {}", e))?;
+
+    let password = uuid::Uuid::new_v4().to_string();
+    redact::register(&password);
+
+    tracing::info!(upstream = %base, "Starting bridge");
+
+    let upstream = Upstream {
+        expected_authorization: authorization(&password)?,
+        target: Target::Http {
+            base,
+            client,
+            auth: profile.auth.clone(),
+        },
+    };
+
+    let url = listen(upstream).await?;
+
+    Ok(Bridge { url, password })
+}
+
+/// Starts a bridge to a sidecar on `socket` that accepts the sidecar's own `password`. The
+/// socket doesn't need to exist yet, requests fail with 502 until the sidecar is listening.
+#[cfg(unix)]
+pub async fn start_unix(socket: &Path, password: &str) -> Result<Bridge, String> {
+    tracing::info!(socket = %socket.display(), "Starting socket bridge");
+
+    let upstream = Upstream {
+        expected_authorization: authorization(password)?,
+        target: Target::Unix(socket.to_path_buf()),
+    };
+
+    let url = listen(upstream).await?;
+
+    Ok(Bridge {
+        url,
+        password: password.to_string(),
+    })
+}
+
+async fn listen(upstream: Upstream) -> Result<String, String> {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Failed to bind bridge: {}", e))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to get bridge address: {}", e))?;
+
+    tracing::info!(%addr, "Bridge listening");
+
+    let upstream = Arc::new(upstream);
+
+    tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Bridge failed to accept connection: {e}");
+                    continue;
+                }
+            };
+
+            let upstream = upstream.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req| forward(upstream.clone(), req));
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .with_upgrades()
+                    .await
+                {
+                    tracing::debug!("Bridge connection closed: {e}");
+                }
+            });
+        }
+    });
+
+    Ok(format!("http://{addr}"))
+}
+
+fn empty(status: StatusCode) -> Response<Body> {
+    let mut res = Response::new(Empty::new().map_err(|never| match never {}).boxed_unsync());
+    *res.status_mut() = status;
+    res
+}
+
+fn strip_hop_by_hop(headers: &mut HeaderMap, keep_upgrade: bool) {
+    for name in HOP_BY_HOP {
+        if keep_upgrade && (*name == header::CONNECTION || *name == header::UPGRADE) {
+            continue;
+        }
+        headers.remove(name);
+    }
+}
+
+/// Copies bytes both ways between an upgraded upstream connection and the client, once both
+/// sides have switched protocols.
+fn splice<U, E>(upstream: impl Future<Output = Result<U, E>> + Send + 'static, client: OnUpgrade)
+where
+    U: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    E: Display + Send + 'static,
+{
+    tokio::spawn(async move {
+        let (mut upstream_io, client_io) = match (upstream.await, client.await) {
+            (Ok(upstream_io), Ok(client_io)) => (upstream_io, client_io),
+            (Err(e), _) => {
+                tracing::warn!("Bridge upstream upgrade failed: {e}");
+                return;
+            }
+            (_, Err(e)) => {
+                tracing::warn!("Bridge client upgrade failed: {e}");
+                return;
+            }
+        };
+
+        let mut client_io = TokioIo::new(client_io);
+        let _ = tokio::io::copy_bidirectional(&mut client_io, &mut upstream_io).await;
+    });
+}
+
+async fn forward(
+    upstream: Arc<Upstream>,
+    req: Request<Incoming>,
+) -> Result<Response<Body>, Infallible> {
+    if req.headers().get(header::AUTHORIZATION) != Some(&upstream.expected_authorization) {
+        return Ok(empty(StatusCode::UNAUTHORIZED));
+    }
+
+    Ok(match &upstream.target {
+        Target::Http { base, client, auth } => forward_http(base, client, auth.as_ref(), req).await,
+        #[cfg(unix)]
+        Target::Unix(socket) => forward_unix(socket, req).await,
+    })
+}
+
+async fn forward_http(
+    base: &reqwest::Url,
+    client: &reqwest::Client,
+    auth: Option<&ServerAuth>,
+    mut req: Request<Incoming>,
+) -> Response<Body> {
+    let mut url = base.clone();
+    url.set_path(&format!(
+        "{}{}",
+        base.path().trim_end_matches('/'),
+        req.uri().path()
+    ));
+    url.set_query(req.uri().query());
+
+    let is_upgrade = req.headers().contains_key(header::UPGRADE);
+    let client_upgrade = is_upgrade.then(|| hyper::upgrade::on(&mut req));
+
+    let (parts, body) = req.into_parts();
+
+    let mut headers = parts.headers;
+    strip_hop_by_hop(&mut headers, is_upgrade);
+    headers.remove(header::HOST);
+    headers.remove(header::AUTHORIZATION);
+
+    let mut builder = client.request(parts.method, url).headers(headers);
+
+    builder = match auth {
+        Some(ServerAuth::Basic { username, password }) => {
+            builder.basic_auth(username, Some(password))
+        }
+        Some(ServerAuth::Bearer { token }) => builder.bearer_auth(token),
+        None => builder,
+    };
+
+    // Requests from the app are small JSON payloads, so buffering them keeps GETs free of a
+    // chunked body. Responses are streamed, since event streams never end.
+    let body = match body.collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) => {
+            tracing::debug!("Bridge failed to read request body: {e}");
+            return empty(StatusCode::BAD_REQUEST);
+        }
+    };
+    if !body.is_empty() {
+        builder = builder.body(body);
+    }
+
+    let upstream_res = match builder.send().await {
+        Ok(res) => res,
+        Err(e) => {
+            tracing::warn!("Bridge request failed: {e}");
+            return empty(StatusCode::BAD_GATEWAY);
+        }
+    };
+
+    let status = upstream_res.status();
+    let mut headers = upstream_res.headers().clone();
+    strip_hop_by_hop(&mut headers, status == StatusCode::SWITCHING_PROTOCOLS);
+
+    let mut res = if status == StatusCode::SWITCHING_PROTOCOLS
+        && let Some(client_upgrade) = client_upgrade
+    {
+        splice(upstream_res.upgrade(), client_upgrade);
+
+        empty(status)
+    } else {
+        let stream = upstream_res
+            .bytes_stream()
+            .map_ok(Frame::data)
+            .map_err(std::io::Error::other);
+        let mut res = Response::new(StreamBody::new(stream).boxed_unsync());
+        *res.status_mut() = status;
+        res
+    };
+
+    *res.headers_mut() = headers;
+    res
+}
+
+#[cfg(unix)]
+async fn forward_unix(socket: &Path, mut req: Request<Incoming>) -> Response<Body> {
+    let is_upgrade = req.headers().contains_key(header::UPGRADE);
+    let client_upgrade = is_upgrade.then(|| hyper::upgrade::on(&mut req));
+
+    let (mut parts, body) = req.into_parts();
+    strip_hop_by_hop(&mut parts.headers, is_upgrade);
+    parts
+        .headers
+        .insert(header::HOST, HeaderValue::from_static("localhost"));
+
+    let body = match body.collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(e) => {
+            tracing::debug!("Bridge failed to read request body: {e}");
+            return empty(StatusCode::BAD_REQUEST);
+        }
+    };
+    let req = Request::from_parts(parts, Full::new(body));
+
+    let stream = match tokio::net::UnixStream::connect(socket).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            tracing::debug!("Bridge failed to connect to sidecar socket: {e}");
+            return empty(StatusCode::BAD_GATEWAY);
+        }
+    };
+
+    let (mut sender, conn) = match hyper::client::conn::http1::handshake(TokioIo::new(stream)).await
+    {
+        Ok(handshake) => handshake,
+        Err(e) => {
+            tracing::warn!("Bridge handshake with sidecar failed: {e}");
+            return empty(StatusCode::BAD_GATEWAY);
+        }
+    };
+    tokio::spawn(async move {
+        if let Err(e) = conn.with_upgrades().await {
+            tracing::debug!("Bridge sidecar connection closed: {e}");
+        }
+    });
+
+    let mut upstream_res = match sender.send_request(req).await {
+        Ok(res) => res,
+        Err(e) => {
+            tracing::warn!("Bridge request failed: {e}");
+            return empty(StatusCode::BAD_GATEWAY);
+        }
+    };
+
+    let status = upstream_res.status();
+    strip_hop_by_hop(
+        upstream_res.headers_mut(),
+        status == StatusCode::SWITCHING_PROTOCOLS,
+    );
+
+    if status == StatusCode::SWITCHING_PROTOCOLS
+        && let Some(client_upgrade) = client_upgrade
+    {
+        let upstream_upgrade = hyper::upgrade::on(&mut upstream_res);
+        splice(
+            async move { upstream_upgrade.await.map(TokioIo::new) },
+            client_upgrade,
+        );
+
+        let mut res = empty(status);
+        *res.headers_mut() = upstream_res.headers().clone();
+        return res;
+    }
+
+    upstream_res.map(|body| body.map_err(std::io::Error::other).boxed_unsync())
+}
@@ -1,10 +1,30 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 use tauri::{AppHandle, Manager, path::BaseDirectory};
 use tauri_plugin_shell::{
     ShellExt,
-    process::{Command, CommandChild, CommandEvent, TerminatedPayload},
+    process::{Command, CommandEvent, TerminatedPayload},
 };
 use tokio::sync::oneshot;
 
+#[cfg(not(target_os = "windows"))]
+use crate::shell_env;
+use crate::{
+    command_error::{CommandError, CommandErrorKind},
+    init, launch_options, log_levels, redact,
+    server::{self, SpawnError},
+    sidecar_log::{self, SidecarStream},
+    sidecar_registry,
+    transport::SidecarAddr,
+};
+
+#[cfg(target_os = "linux")]
+pub use crate::process_group::SidecarChild;
+#[cfg(not(target_os = "linux"))]
+pub use tauri_plugin_shell::process::CommandChild as SidecarChild;
+
 const CLI_INSTALL_DIR: &str = ".opencode/bin";
 const CLI_BINARY_NAME: &str = "opencode";
 
@@ -20,7 +40,10 @@ pub struct Config {
 }
 
 pub async fn get_config(app: &AppHandle) -> Option<Config> {
-    create_command(app, "debug config")
+    #[cfg(not(target_os = "windows"))]
+    shell_env::warm().await;
+
+    create_command(app, ["debug", "config"])
         .output()
         .await
         .inspect_err(|e| tracing::warn!("Failed to read OC config: {e}"))
@@ -56,47 +79,72 @@ const INSTALL_SCRIPT: &str = include_str!("../../../../install");
 
 #[tauri::command]
 #[specta::specta]
-pub fn install_cli(app: tauri::AppHandle) -> Result<String, String> {
+pub fn install_cli(app: tauri::AppHandle) -> Result<String, CommandError> {
     if cfg!(not(unix)) {
-        return Err("CLI installation is only supported on macOS & Linux".to_string());
+        return Err(CommandError::unsupported(
+            "CLI installation is only supported on macOS & Linux",
+        ));
     }
 
     let sidecar = get_sidecar_path(&app);
     if !sidecar.exists() {
-        return Err("Sidecar binary not found".to_string());
+        return Err(CommandError::new(
+            CommandErrorKind::SidecarMissing {
+                path: sidecar.display().to_string(),
+            },
+            "Sidecar binary not found",
+        ));
     }
 
     let temp_script = std::env::temp_dir().join("opencode-install.sh");
-    std::fs::write(&temp_script, INSTALL_SCRIPT)
-        .map_err(|e| format!("Failed to write install script: {}", e))?;
+    std::fs::write(&temp_script, INSTALL_SCRIPT).map_err(|e| {
+        CommandError::io(
+            &temp_script,
+            format!("Failed to write install script: {}", e),
+        )
+    })?;
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(&temp_script, std::fs::Permissions::from_mode(0o755))
-            .map_err(|e| format!("Failed to set script permissions: {}", e))?;
+        std::fs::set_permissions(&temp_script, std::fs::Permissions::from_mode(0o755)).map_err(
+            |e| {
+                CommandError::io(
+                    &temp_script,
+                    format!("Failed to set script permissions: {}", e),
+                )
+            },
+        )?;
     }
 
     let output = std::process::Command::new(&temp_script)
         .arg("--binary")
         .arg(&sidecar)
         .output()
-        .map_err(|e| format!("Failed to run install script: {}", e))?;
+        .map_err(|e| {
+            CommandError::io(&temp_script, format!("Failed to run install script: {}", e))
+        })?;
 
     let _ = std::fs::remove_file(&temp_script);
 
     if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Install script failed: {}", stderr));
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        return Err(CommandError::new(
+            CommandErrorKind::ProcessFailed {
+                status: output.status.code(),
+                stderr: stderr.clone(),
+            },
+            format!("Install script failed: {}", stderr),
+        ));
     }
 
-    let install_path =
-        get_cli_install_path().ok_or_else(|| "Could not determine install path".to_string())?;
+    let install_path = get_cli_install_path()
+        .ok_or_else(|| CommandError::internal("Could not determine install path"))?;
 
     Ok(install_path.to_string_lossy().to_string())
 }
 
-pub fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
+pub fn sync_cli(app: tauri::AppHandle) -> Result<(), CommandError> {
     if cfg!(debug_assertions) {
         tracing::debug!("Skipping CLI sync for debug build");
         return Ok(());
@@ -107,21 +155,31 @@ pub fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
         return Ok(());
     }
 
-    let cli_path =
-        get_cli_install_path().ok_or_else(|| "Could not determine CLI install path".to_string())?;
+    let cli_path = get_cli_install_path()
+        .ok_or_else(|| CommandError::internal("Could not determine CLI install path"))?;
 
     let output = std::process::Command::new(&cli_path)
         .arg("--version")
         .output()
-        .map_err(|e| format!("Failed to get CLI version: {}", e))?;
+        .map_err(|e| CommandError::io(&cli_path, format!("Failed to get CLI version: {}", e)))?;
 
     if !output.status.success() {
-        return Err("Failed to get CLI version".to_string());
+        return Err(CommandError::new(
+            CommandErrorKind::ProcessFailed {
+                status: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            },
+            "Failed to get CLI version",
+        ));
     }
 
     let cli_version_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    let cli_version = semver::Version::parse(&cli_version_str)
-        .map_err(|e| format!("Failed to parse CLI version '{}': {}", cli_version_str, e))?;
+    let cli_version = semver::Version::parse(&cli_version_str).map_err(|e| {
+        CommandError::internal(format!(
+            "Failed to parse CLI version '{}': {}",
+            cli_version_str, e
+        ))
+    })?;
 
     let app_version = app.package_info().version.clone();
 
@@ -145,90 +203,170 @@ pub fn sync_cli(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
-fn get_user_shell() -> String {
-    std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
-}
-
-pub fn create_command(app: &tauri::AppHandle, args: &str) -> Command {
+/// The sidecar command, with the login-shell environment as last captured. Async callers
+/// should `shell_env::warm().await` first, so it's captured and up to date.
+pub fn create_command<I, S>(app: &tauri::AppHandle, args: I) -> Command
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
     let state_dir = app
         .path()
         .resolve("", BaseDirectory::AppLocalData)
         .expect("Failed to resolve app local data dir");
+    let options = launch_options::read(app);
+    redact::register_env(&options.env);
 
     #[cfg(target_os = "windows")]
     return app
         .shell()
         .sidecar("opencode-cli")
         .unwrap()
-        .args(args.split_whitespace())
-        .env("OPENCODE_EXPERIMENTAL_ICON_DISCOVERY", "true")
-        .env("OPENCODE_EXPERIMENTAL_FILEWATCHER", "true")
+        .args(args.into_iter().map(|arg| arg.as_ref().to_string()))
+        .envs(options.env)
+        .envs(options.flag_env())
         .env("OPENCODE_CLIENT", "desktop")
         .env("XDG_STATE_HOME", &state_dir);
 
     #[cfg(not(target_os = "windows"))]
     return {
-        let sidecar = get_sidecar_path(app);
-        let shell = get_user_shell();
-
-        let cmd = if shell.ends_with("/nu") {
-            format!("^\"{}\" {}", sidecar.display(), args)
-        } else {
-            format!("\"{}\" {}", sidecar.display(), args)
-        };
-
-        app.shell()
-            .command(&shell)
-            .env("OPENCODE_EXPERIMENTAL_ICON_DISCOVERY", "true")
-            .env("OPENCODE_EXPERIMENTAL_FILEWATCHER", "true")
+        let mut command = app
+            .shell()
+            .command(get_sidecar_path(app))
+            .args(args.into_iter().map(|arg| arg.as_ref().to_string()));
+
+        // Never captures, callers warm the cache first
+        if let Some(shell_env) = shell_env::cached() {
+            redact::register_env(&shell_env);
+            command = command.envs(shell_env);
+        }
+
+        // Profiles may set their own proxy exclusions, keep the loopback ones added at startup
+        for key in ["NO_PROXY", "no_proxy"] {
+            if let Ok(value) = std::env::var(key) {
+                command = command.env(key, value);
+            }
+        }
+
+        // Env entries saved before the toggles' names were rejected mustn't override them
+        command
+            .envs(options.env)
+            .envs(options.flag_env())
             .env("OPENCODE_CLIENT", "desktop")
             .env("XDG_STATE_HOME", &state_dir)
-            .args(["-il", "-c", &cmd])
     };
 }
 
+/// Whether a line of sidecar output reports that its port was already taken.
+fn is_port_in_use_error(line: &str) -> bool {
+    let line = line.to_lowercase();
+    line.contains("eaddrinuse")
+        || line.contains("address already in use")
+        || line.contains("failed to start server on port")
+}
+
+/// Spawns `opencode serve`. Besides the child and its exit, returns a flag that is set if the
+/// sidecar reports that it couldn't bind its port.
 pub fn serve(
     app: &AppHandle,
-    hostname: &str,
-    port: u32,
+    addr: &SidecarAddr,
     password: &str,
-) -> (CommandChild, oneshot::Receiver<TerminatedPayload>) {
+) -> Result<
+    (
+        SidecarChild,
+        oneshot::Receiver<TerminatedPayload>,
+        Arc<AtomicBool>,
+    ),
+    SpawnError,
+> {
+    let binary = get_sidecar_path(app)
+        .with_file_name(format!("opencode-cli{}", std::env::consts::EXE_SUFFIX));
+    if !binary.exists() {
+        return Err(SpawnError::Missing(binary));
+    }
+
     let (exit_tx, exit_rx) = oneshot::channel::<TerminatedPayload>();
+    let port_in_use = Arc::new(AtomicBool::new(false));
 
-    tracing::info!(port, "Spawning sidecar");
+    let options = launch_options::read(app);
+    launch_options::mark_applied(&options);
+    let log_level = log_levels::sidecar_level(options.log_level);
+
+    // Extra env vars may hold credentials, so only their names are logged
+    tracing::info!(
+        ?addr,
+        ?log_level,
+        env = ?options.env.keys().collect::<Vec<_>>(),
+        args = ?options.args,
+        "Spawning sidecar"
+    );
+
+    let mut args = vec![
+        "--print-logs".to_string(),
+        "--log-level".to_string(),
+        log_level.as_arg().to_string(),
+        "serve".to_string(),
+    ];
+    args.extend(addr.serve_args());
+    args.extend(options.args);
+
+    let command = create_command(app, args)
+        .env("OPENCODE_SERVER_USERNAME", server::SIDECAR_USERNAME)
+        .env("OPENCODE_SERVER_PASSWORD", password);
+
+    // On Linux the sidecar gets its own process group so it can't outlive the app
+    #[cfg(target_os = "linux")]
+    let (mut rx, child) = {
+        let limits = crate::resource_limits::read(app);
+        let (rx, child) = crate::process_group::spawn(command.into(), &limits)
+            .map_err(|e| SpawnError::Spawn(e.to_string()))?;
+        crate::resource_limits::spawn_monitor(app.clone(), &child, limits);
+        (rx, child)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let (mut rx, child) = command
+        .spawn()
+        .map_err(|e| SpawnError::Spawn(e.to_string()))?;
 
-    let (mut rx, child) = create_command(
-        app,
-        format!("--print-logs --log-level WARN serve --hostname {hostname} --port {port}").as_str(),
-    )
-    .env("OPENCODE_SERVER_USERNAME", "opencode")
-    .env("OPENCODE_SERVER_PASSWORD", password)
-    .spawn()
-    .expect("Failed to spawn opencode");
+    let pid = child.pid();
+    sidecar_registry::record_spawn(app, pid, addr.port());
+    sidecar_log::started(pid);
 
+    let app = app.clone();
+    let port_conflict = port_in_use.clone();
     tokio::spawn(async move {
         let mut exit_tx = Some(exit_tx);
         while let Some(event) = rx.recv().await {
+            let stream = match event {
+                CommandEvent::Stderr(_) => SidecarStream::Stderr,
+                _ => SidecarStream::Stdout,
+            };
             match event {
-                CommandEvent::Stdout(line_bytes) => {
+                CommandEvent::Stdout(line_bytes) | CommandEvent::Stderr(line_bytes) => {
                     let line = String::from_utf8_lossy(&line_bytes);
-                    tracing::info!(target: "sidecar", "{line}");
-                }
-                CommandEvent::Stderr(line_bytes) => {
-                    let line = String::from_utf8_lossy(&line_bytes);
-                    tracing::info!(target: "sidecar", "{line}");
+                    sidecar_log::record(pid, stream, &line);
+
+                    if is_port_in_use_error(&line) {
+                        port_conflict.store(true, Ordering::SeqCst);
+                    }
+
+                    if let Some((current, total)) = init::parse_migration(&line) {
+                        init::migration_progress(&app, current, total);
+                    }
                 }
                 CommandEvent::Error(err) => {
-                    tracing::error!(target: "sidecar", "{err}");
+                    tracing::error!(pid, "Sidecar output error: {err}");
                 }
                 CommandEvent::Terminated(payload) => {
                     tracing::info!(
-                        target: "sidecar",
+                        pid,
                         code = ?payload.code,
                         signal = ?payload.signal,
                         "Sidecar terminated"
                     );
 
+                    sidecar_registry::record_exit(&app, pid);
+
                     if let Some(tx) = exit_tx.take() {
                         let _ = tx.send(payload);
                     }
@@ -238,5 +376,5 @@ pub fn serve(
         }
     });
 
-    (child, exit_rx)
+    Ok((child, exit_rx, port_in_use))
 }
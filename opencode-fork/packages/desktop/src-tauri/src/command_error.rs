@@ -0,0 +1,128 @@
+//! The error type returned by the app's Tauri commands.
+
+use std::fmt::Display;
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum CommandErrorKind {
+    /// The settings store couldn't be opened, read or saved.
+    Settings,
+    /// An argument was rejected. `field` is the name of the offending input, as the frontend
+    /// knows it.
+    InvalidInput {
+        field: String,
+    },
+    InvalidUrl {
+        url: String,
+    },
+    /// No server profile has the given id.
+    ProfileNotFound {
+        id: String,
+    },
+    /// The command isn't available on this platform.
+    Unsupported {
+        platform: String,
+    },
+    /// The sidecar binary isn't next to the app.
+    SidecarMissing {
+        path: String,
+    },
+    /// The app doesn't run a local server, e.g. because it's connected to a remote one.
+    ServerNotRunning,
+    /// The local server is being shut down.
+    ServerStopping,
+    /// A file couldn't be read or written.
+    Io {
+        path: String,
+    },
+    /// A helper process ran but failed. `status` is its exit code.
+    ProcessFailed {
+        status: Option<i32>,
+        stderr: String,
+    },
+    /// Anything the frontend has no reason to tell apart.
+    Internal,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+pub struct CommandError {
+    pub kind: CommandErrorKind,
+    /// Description of the error that can be shown as is.
+    pub message: String,
+}
+
+impl CommandError {
+    pub fn new(kind: CommandErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    pub fn settings_open(err: impl Display) -> Self {
+        Self::new(
+            CommandErrorKind::Settings,
+            format!("Failed to open settings store: {}", err),
+        )
+    }
+
+    pub fn settings_save(err: impl Display) -> Self {
+        Self::new(
+            CommandErrorKind::Settings,
+            format!("Failed to save settings: {}", err),
+        )
+    }
+
+    pub fn invalid_input(field: &str, message: impl Into<String>) -> Self {
+        Self::new(
+            CommandErrorKind::InvalidInput {
+                field: field.to_string(),
+            },
+            message,
+        )
+    }
+
+    pub fn invalid_url(url: &str, err: impl Display) -> Self {
+        Self::new(
+            CommandErrorKind::InvalidUrl {
+                url: url.to_string(),
+            },
+            format!("Invalid server URL '{}': {}", url, err),
+        )
+    }
+
+    pub fn profile_not_found(id: &str) -> Self {
+        Self::new(
+            CommandErrorKind::ProfileNotFound { id: id.to_string() },
+            format!("Server profile '{}' not found", id),
+        )
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::new(
+            CommandErrorKind::Unsupported {
+                platform: std::env::consts::OS.to_string(),
+            },
+            message,
+        )
+    }
+
+    pub fn io(path: &std::path::Path, message: impl Into<String>) -> Self {
+        Self::new(
+            CommandErrorKind::Io {
+                path: path.display().to_string(),
+            },
+            message,
+        )
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(CommandErrorKind::Internal, message)
+    }
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
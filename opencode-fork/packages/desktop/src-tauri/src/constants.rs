@@ -2,6 +2,13 @@ use tauri_plugin_window_state::StateFlags;
 
 pub const SETTINGS_STORE: &str = "opencode.settings.dat";
 pub const DEFAULT_SERVER_URL_KEY: &str = "defaultServerUrl";
+pub const SERVER_PROFILES_KEY: &str = "serverProfiles";
+pub const HEALTH_CHECK_INTERVAL_KEY: &str = "healthCheckIntervalMs";
+pub const SHUTDOWN_GRACE_PERIOD_KEY: &str = "shutdownGracePeriodMs";
+pub const SIDECAR_PORT_KEY: &str = "sidecarPort";
+pub const SIDECAR_RESOURCE_LIMITS_KEY: &str = "sidecarResourceLimits";
+pub const SIDECAR_LAUNCH_OPTIONS_KEY: &str = "sidecarLaunchOptions";
+pub const LOG_RETENTION_KEY: &str = "logRetention";
 pub const UPDATER_ENABLED: bool = option_env!("TAURI_SIGNING_PRIVATE_KEY").is_some();
 
 pub fn window_state_flags() -> StateFlags {
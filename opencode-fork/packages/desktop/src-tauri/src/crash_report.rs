@@ -0,0 +1,314 @@
+//! Crash reports for panics.
+//!
+//! A panic hook writes what's known at the time of the panic to the app data dir, before the
+//! default hook runs. On the next launch the frontend lists the reports that haven't been saved
+//! yet, so they can be looked at, saved for a bug report or deleted.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::oneshot;
+
+use crate::{command_error::CommandError, init, logging, redact, sidecar_log};
+
+const CRASH_DIR: &str = "crash-reports";
+
+/// Lines of each log included in a report.
+const LOG_LINES: usize = 100;
+
+/// How far back from the end of the desktop log lines are looked for.
+const LOG_TAIL_BYTES: u64 = 64 * 1024;
+
+/// Reports kept, saved or not. Older ones are deleted at startup.
+const MAX_REPORTS: usize = 20;
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CrashReport {
+    /// RFC 3339.
+    timestamp: String,
+    app_version: String,
+    os: String,
+    thread: Option<String>,
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+    /// Unset if the panic happened before startup began.
+    init_phase: Option<String>,
+    desktop_log: Vec<String>,
+    sidecar_log: Vec<String>,
+    /// Set once the report was saved somewhere, after which it's no longer offered.
+    #[serde(default)]
+    saved: bool,
+}
+
+impl CrashReport {
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "OpenCode crashed at {}\n\n\
+            App version: {}\n\
+            OS: {}\n\
+            Thread: {}\n\
+            Startup phase: {}\n\
+            Location: {}\n\n\
+            {}\n\n\
+            Backtrace:\n{}\n",
+            self.timestamp,
+            self.app_version,
+            self.os,
+            self.thread.as_deref().unwrap_or("unnamed"),
+            self.init_phase.as_deref().unwrap_or("not started"),
+            self.location.as_deref().unwrap_or("unknown"),
+            self.message,
+            self.backtrace,
+        );
+
+        for (title, lines) in [
+            ("Desktop log", &self.desktop_log),
+            ("Sidecar output", &self.sidecar_log),
+        ] {
+            text.push_str(&format!("\n{title}:\n"));
+            for line in lines {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+
+        text
+    }
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReportSummary {
+    pub id: String,
+    /// RFC 3339.
+    pub timestamp: String,
+    /// The panic message.
+    pub message: String,
+    pub saved: bool,
+}
+
+/// Installs the panic hook, which writes reports to the app data dir. The default hook still
+/// runs afterwards.
+pub fn install(app: &AppHandle) {
+    let Ok(dir) = app.path().app_data_dir().map(|dir| dir.join(CRASH_DIR)) else {
+        tracing::warn!("No app data dir, crash reports are disabled");
+        return;
+    };
+    let _ = DIR.set(dir.clone());
+    prune(&dir);
+
+    let app = app.clone();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let location = info.location().map(|location| location.to_string());
+
+        tracing::error!(
+            location = location.as_deref().unwrap_or_default(),
+            "Panic: {message}"
+        );
+
+        let report = CrashReport {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            app_version: app.package_info().version.to_string(),
+            os: format!(
+                "{} {} {}",
+                tauri_plugin_os::platform(),
+                tauri_plugin_os::version(),
+                tauri_plugin_os::arch()
+            ),
+            thread: std::thread::current().name().map(String::from),
+            message: redact::redact(&message),
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            init_phase: init::phase(&app).map(|phase| format!("{phase:?}")),
+            // A panic while one of these locks is held would deadlock on them, so they're
+            // only tried
+            desktop_log: logging::try_path()
+                .and_then(|path| logging::tail(&path, None, LOG_TAIL_BYTES, LOG_LINES).ok())
+                .map(|lines| lines.into_iter().map(|line| line.text).collect())
+                .unwrap_or_default(),
+            sidecar_log: sidecar_log::try_recent(LOG_LINES)
+                .into_iter()
+                .map(|line| line.line)
+                .collect(),
+            saved: false,
+        };
+        if let Err(e) = write(&dir, &report) {
+            eprintln!("Failed to write crash report: {e}");
+        }
+
+        previous(info);
+    }));
+}
+
+/// Deletes all but the newest [`MAX_REPORTS`] reports.
+fn prune(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut reports: Vec<_> = entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| name.starts_with("crash_") && name.ends_with(".json"))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    // Ids contain the time of the crash
+    reports.sort();
+
+    let excess = reports.len().saturating_sub(MAX_REPORTS);
+    for path in &reports[..excess] {
+        match std::fs::remove_file(path) {
+            Ok(()) => tracing::debug!(path = %path.display(), "Deleted old crash report"),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), "Failed to delete old crash report: {e}")
+            }
+        }
+    }
+}
+
+fn write(dir: &Path, report: &CrashReport) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let id = format!(
+        "crash_{}",
+        chrono::Local::now().format("%Y-%m-%d_%H-%M-%S%.3f")
+    );
+    let json = serde_json::to_vec_pretty(report).map_err(std::io::Error::other)?;
+
+    std::fs::write(dir.join(format!("{id}.json")), json)
+}
+
+fn dir() -> Result<&'static PathBuf, CommandError> {
+    DIR.get()
+        .ok_or_else(|| CommandError::internal("Crash reports are disabled"))
+}
+
+/// The path of a report by id, refusing anything that isn't one.
+fn resolve(id: &str) -> Result<PathBuf, CommandError> {
+    let valid = id.starts_with("crash_")
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !id.contains("..");
+    if !valid {
+        return Err(CommandError::invalid_input(
+            "id",
+            format!("'{}' isn't a crash report", id),
+        ));
+    }
+
+    Ok(dir()?.join(format!("{id}.json")))
+}
+
+fn read(path: &Path) -> Result<CrashReport, CommandError> {
+    let raw = std::fs::read(path)
+        .map_err(|e| CommandError::io(path, format!("Failed to read crash report: {}", e)))?;
+
+    serde_json::from_slice(&raw)
+        .map_err(|e| CommandError::io(path, format!("Invalid crash report: {}", e)))
+}
+
+/// Crash reports, newest first. Reports that were saved are included but marked as such.
+#[tauri::command]
+#[specta::specta]
+pub fn list_crash_reports() -> Result<Vec<CrashReportSummary>, CommandError> {
+    let dir = dir()?;
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(vec![]);
+    };
+
+    let mut reports: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let id = name.strip_suffix(".json")?.to_string();
+            let report = read(&entry.path())
+                .inspect_err(|e| tracing::warn!("Skipping crash report {id}: {e}"))
+                .ok()?;
+
+            Some(CrashReportSummary {
+                id,
+                timestamp: report.timestamp,
+                message: report.message,
+                saved: report.saved,
+            })
+        })
+        .collect();
+
+    // Ids contain the time of the crash
+    reports.sort_by(|a, b| b.id.cmp(&a.id));
+
+    Ok(reports)
+}
+
+/// The report as text, the way it's saved.
+#[tauri::command]
+#[specta::specta]
+pub fn read_crash_report(id: String) -> Result<String, CommandError> {
+    Ok(read(&resolve(&id)?)?.to_text())
+}
+
+/// Asks where to save the report as text and writes it there, after which it's no longer
+/// offered. Returns the path it was saved to, or nothing if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn save_crash_report(app: AppHandle, id: String) -> Result<Option<String>, CommandError> {
+    let path = resolve(&id)?;
+    let mut report = read(&path)?;
+
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("Save Crash Report")
+        .set_file_name(format!("opencode-{id}.txt"))
+        .add_filter("Text", &["txt"])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(target) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let target = target
+        .into_path()
+        .map_err(|e| CommandError::internal(format!("Invalid save location: {}", e)))?;
+
+    std::fs::write(&target, report.to_text())
+        .map_err(|e| CommandError::io(&target, format!("Failed to save crash report: {}", e)))?;
+
+    report.saved = true;
+    let json =
+        serde_json::to_vec_pretty(&report).map_err(|e| CommandError::internal(e.to_string()))?;
+    std::fs::write(&path, json)
+        .map_err(|e| CommandError::io(&path, format!("Failed to update crash report: {}", e)))?;
+
+    Ok(Some(target.display().to_string()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_crash_report(id: String) -> Result<(), CommandError> {
+    let path = resolve(&id)?;
+
+    std::fs::remove_file(&path)
+        .map_err(|e| CommandError::io(&path, format!("Failed to delete crash report: {}", e)))
+}
@@ -0,0 +1,334 @@
+//! A single archive with what's needed to look into a problem, for attaching to bug reports.
+//!
+//! It holds the recent log files, the versions of everything involved, how the app connected to
+//! its server, the OpenCode config and the settings store. Credentials, keys and tokens are
+//! replaced before anything is written.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use flate2::read::GzDecoder;
+use serde_json::{Value, json};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_store::StoreExt;
+use tokio::sync::oneshot;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+#[cfg(not(target_os = "windows"))]
+use crate::shell_env;
+use crate::{
+    ServerState, cli,
+    command_error::CommandError,
+    constants::SETTINGS_STORE,
+    logging,
+    profiles::ServerAuth,
+    redact::{self, REDACTED},
+};
+
+/// Log files included per source, newest first. Rotated parts count separately.
+const RECENT_LOG_FILES: usize = 4;
+
+/// How much of the end of each uncompressed log file is included. Current files can be large,
+/// rotation only caps them at their next write.
+const MAX_LOG_BYTES: u64 = 16 * 1024 * 1024;
+
+/// How long `opencode debug config` and `--version` may take before they're left out.
+const DEBUG_CONFIG_TIMEOUT: Duration = Duration::from_secs(20);
+
+async fn sidecar_version(app: &AppHandle) -> Option<String> {
+    #[cfg(not(target_os = "windows"))]
+    shell_env::warm().await;
+
+    let output = tokio::time::timeout(
+        DEBUG_CONFIG_TIMEOUT,
+        cli::create_command(app, ["--version"]).output(),
+    )
+    .await
+    .inspect_err(|_| tracing::warn!("Timed out getting sidecar version"))
+    .ok()?
+    .inspect_err(|e| tracing::warn!("Failed to get sidecar version: {e}"))
+    .ok()?;
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The output of `opencode debug config`, as JSON if it parses and as printed otherwise.
+async fn debug_config(app: &AppHandle) -> (&'static str, Vec<u8>) {
+    #[cfg(not(target_os = "windows"))]
+    shell_env::warm().await;
+
+    let output = tokio::time::timeout(
+        DEBUG_CONFIG_TIMEOUT,
+        cli::create_command(app, ["debug", "config"]).output(),
+    )
+    .await;
+
+    let output = match output {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            return (
+                "debug-config.txt",
+                format!("Failed to run: {e}").into_bytes(),
+            );
+        }
+        Err(_) => {
+            return (
+                "debug-config.txt",
+                format!("Timed out after {}s", DEBUG_CONFIG_TIMEOUT.as_secs()).into_bytes(),
+            );
+        }
+    };
+
+    match serde_json::from_slice::<Value>(&output.stdout) {
+        Ok(mut config) => {
+            redact::redact_json(&mut config);
+            (
+                "debug-config.json",
+                serde_json::to_vec_pretty(&config).unwrap_or_default(),
+            )
+        }
+        Err(_) => {
+            let text = format!(
+                "Exit status: {:?}\n\n{}\n{}",
+                output.status.code(),
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            ("debug-config.txt", redact::redact(&text).into_bytes())
+        }
+    }
+}
+
+/// What the display backend was resolved to at startup, see `configure_display_backend` in
+/// `main.rs`.
+#[cfg(target_os = "linux")]
+fn display() -> Value {
+    let var = |key: &str| std::env::var(key).ok();
+    let backend = match var("GDK_BACKEND").as_deref() {
+        Some(backend) => backend.to_string(),
+        None if var("WAYLAND_DISPLAY").is_some() => "wayland".to_string(),
+        None => "x11".to_string(),
+    };
+
+    json!({
+        "backend": backend,
+        "preferWayland": crate::linux_display::read_wayland(),
+        "sessionType": var("XDG_SESSION_TYPE"),
+        "waylandDisplay": var("WAYLAND_DISPLAY"),
+        "display": var("DISPLAY"),
+        "winitUnixBackend": var("WINIT_UNIX_BACKEND"),
+        "webkitDisableDmabufRenderer": var("WEBKIT_DISABLE_DMABUF_RENDERER"),
+        "allowWayland": var("OC_ALLOW_WAYLAND"),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn display() -> Value {
+    Value::Null
+}
+
+/// The server the app ended up connected to, or why it couldn't get one.
+fn server_connection(app: &AppHandle) -> Value {
+    let Some(state) = app.try_state::<ServerState>() else {
+        return Value::Null;
+    };
+
+    match state.status.peek() {
+        None => json!({ "status": "starting" }),
+        Some(Err(_)) => json!({ "status": "unknown" }),
+        Some(Ok(Err(error))) => {
+            let mut error = serde_json::to_value(error).unwrap_or_default();
+            redact::redact_json(&mut error);
+            json!({ "status": "failed", "error": error })
+        }
+        Some(Ok(Ok(ready))) => {
+            let auth = ready.auth.as_ref().map(|auth| match auth {
+                ServerAuth::Basic { username, .. } => {
+                    json!({ "type": "basic", "username": username, "password": REDACTED })
+                }
+                ServerAuth::Bearer { .. } => json!({ "type": "bearer", "token": REDACTED }),
+            });
+            let local = state
+                .endpoint()
+                .is_some_and(|endpoint| endpoint.url == ready.url);
+
+            json!({
+                "status": "connected",
+                "url": redact::redact(&ready.url),
+                "auth": auth,
+                "local": local,
+                "sidecarPid": state.child_pid(),
+            })
+        }
+    }
+}
+
+fn settings(app: &AppHandle) -> Value {
+    let entries = match app.store(SETTINGS_STORE) {
+        Ok(store) => store.entries(),
+        Err(e) => return json!({ "error": e.to_string() }),
+    };
+
+    let mut settings = Value::Object(entries.into_iter().collect());
+    redact::redact_json(&mut settings);
+    settings
+}
+
+/// The newest log files of each source.
+fn recent_log_files(log_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(log_dir) else {
+        return vec![];
+    };
+
+    let mut files: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            logging::is_log_file(&name).then_some((name, modified, entry.path()))
+        })
+        .collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+
+    logging::LOG_PREFIXES
+        .iter()
+        .flat_map(|prefix| {
+            files
+                .iter()
+                .filter(|(name, ..)| name.starts_with(prefix))
+                .take(RECENT_LOG_FILES)
+                .map(|(.., path)| path.clone())
+        })
+        .collect()
+}
+
+fn write_archive(
+    path: &Path,
+    documents: Vec<(&str, Vec<u8>)>,
+    log_files: Vec<PathBuf>,
+) -> zip::result::ZipResult<()> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (name, contents) in documents {
+        zip.start_file(name, options)?;
+        zip.write_all(&contents)?;
+    }
+
+    for log_file in log_files {
+        let Some(name) = log_file.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        // Logs written before redaction was in place may still hold secrets
+        match name.strip_suffix(".gz") {
+            // Rotated files are unpacked, their lines are redacted like the others
+            Some(name) => {
+                // Files can go away while the archive is written, when they're rotated or
+                // cleaned up
+                let Ok(file) = File::open(&log_file) else {
+                    continue;
+                };
+                let mut reader = BufReader::new(GzDecoder::new(file));
+
+                zip.start_file(format!("logs/{name}"), options)?;
+                let mut line = vec![];
+                while reader.read_until(b'\n', &mut line)? > 0 {
+                    zip.write_all(redact::redact(&String::from_utf8_lossy(&line)).as_bytes())?;
+                    line.clear();
+                }
+            }
+            None => {
+                let Ok(lines) = logging::tail(&log_file, None, MAX_LOG_BYTES, usize::MAX) else {
+                    continue;
+                };
+
+                zip.start_file(format!("logs/{name}"), options)?;
+                for line in lines {
+                    zip.write_all(redact::redact(&line.text).as_bytes())?;
+                    zip.write_all(b"\n")?;
+                }
+            }
+        }
+    }
+
+    zip.finish()?;
+
+    Ok(())
+}
+
+/// Asks where to save a diagnostics archive and writes it there. Returns the path it was
+/// saved to, or nothing if the dialog was cancelled.
+#[tauri::command]
+#[specta::specta]
+pub async fn export_diagnostics(app: AppHandle) -> Result<Option<String>, CommandError> {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("Export Diagnostics")
+        .set_file_name(format!("opencode-diagnostics_{timestamp}.zip"))
+        .add_filter("Zip archive", &["zip"])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+
+    let Some(path) = rx.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path
+        .into_path()
+        .map_err(|e| CommandError::internal(format!("Invalid save location: {}", e)))?;
+
+    let system = json!({
+        "generatedAt": chrono::Utc::now().to_rfc3339(),
+        "appVersion": app.package_info().version.to_string(),
+        "sidecarVersion": sidecar_version(&app).await,
+        "webviewVersion": tauri::webview_version().ok(),
+        "os": {
+            "platform": tauri_plugin_os::platform(),
+            "version": tauri_plugin_os::version().to_string(),
+            "arch": tauri_plugin_os::arch(),
+        },
+        "display": display(),
+        "server": server_connection(&app),
+        "logFilter": logging::filter(),
+    });
+    let (config_name, config) = debug_config(&app).await;
+
+    let documents = vec![
+        (
+            "system.json",
+            serde_json::to_vec_pretty(&system).unwrap_or_default(),
+        ),
+        (config_name, config),
+        (
+            "settings.json",
+            serde_json::to_vec_pretty(&settings(&app)).unwrap_or_default(),
+        ),
+    ];
+    let log_files = match app.path().app_log_dir() {
+        Ok(dir) => recent_log_files(&dir),
+        Err(_) => vec![],
+    };
+
+    let archive = path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        write_archive(&archive, documents, log_files).inspect_err(|_| {
+            let _ = std::fs::remove_file(&archive);
+        })
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+    .map_err(|e| CommandError::io(&path, format!("Failed to write diagnostics: {}", e)))?;
+
+    tracing::info!(path = %path.display(), "Exported diagnostics");
+
+    Ok(Some(path.display().to_string()))
+}
@@ -0,0 +1,269 @@
+//! Finds OpenCode servers already running on this machine, such as `opencode serve` or a TUI,
+//! so the desktop app can attach to one instead of spawning its own sidecar.
+//!
+//! Two sources are combined: the discovery files servers write to
+//! `$XDG_STATE_HOME/opencode/servers/<pid>.json`, and listening sockets owned by processes
+//! named `opencode*` (older servers don't write a discovery file). Files left behind by servers
+//! that were killed are removed when they're found.
+//!
+//! Attaching makes the server the default profile, after which the frontend relaunches the app
+//! to connect to it the way it connects to any configured server.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::PathBuf,
+};
+
+use futures::future;
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    ServerState,
+    command_error::CommandError,
+    profiles::{self, ServerAuth, ServerProfile},
+    server::{self, HealthStatus},
+};
+
+#[derive(Clone, Copy, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoverySource {
+    File,
+    Socket,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredServer {
+    pub url: String,
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub version: Option<String>,
+    /// Working directory the server was started in, if it published one.
+    pub directory: Option<String>,
+    pub source: DiscoverySource,
+    pub status: HealthStatus,
+}
+
+#[derive(serde::Deserialize)]
+struct DiscoveryFile {
+    pid: u32,
+    hostname: String,
+    port: u16,
+    version: Option<String>,
+    directory: Option<String>,
+    /// Milliseconds since the epoch.
+    #[serde(rename = "startedAt")]
+    started_at: Option<u64>,
+}
+
+/// Whether the server that wrote `file` is still running, rather than a later process that
+/// reused its pid.
+fn is_running(system: &mut System, file: &DiscoveryFile) -> bool {
+    let pid = Pid::from_u32(file.pid);
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing(),
+    );
+
+    system.process(pid).is_some_and(|process| {
+        // Start times are in seconds, allow for rounding
+        file.started_at
+            .is_none_or(|started_at| process.start_time() <= started_at / 1000 + 1)
+    })
+}
+
+fn discovery_dir() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME").filter(|v| !v.is_empty()) {
+        Some(v) => PathBuf::from(v),
+        None => dirs::home_dir()?.join(".local").join("state"),
+    };
+
+    Some(state_home.join("opencode").join("servers"))
+}
+
+/// Wildcard binds are reachable over loopback.
+fn connect_ip(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    }
+}
+
+fn url_for(ip: IpAddr, port: u16) -> String {
+    match connect_ip(ip) {
+        IpAddr::V4(ip) => format!("http://{ip}:{port}"),
+        IpAddr::V6(ip) => format!("http://[{ip}]:{port}"),
+    }
+}
+
+fn from_files() -> Vec<DiscoveredServer> {
+    let Some(dir) = discovery_dir() else {
+        return vec![];
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return vec![];
+    };
+    let mut system = System::new();
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| {
+            let contents = std::fs::read_to_string(entry.path()).ok()?;
+            let file: DiscoveryFile = serde_json::from_str(&contents)
+                .inspect_err(|e| {
+                    tracing::debug!(path = %entry.path().display(), "Invalid discovery file: {e}")
+                })
+                .ok()?;
+
+            // Servers that were killed don't get to remove their file
+            if !is_running(&mut system, &file) {
+                tracing::debug!(path = %entry.path().display(), pid = file.pid, "Removing stale discovery file");
+                let _ = std::fs::remove_file(entry.path());
+                return None;
+            }
+
+            let ip = file
+                .hostname
+                .parse::<IpAddr>()
+                .unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+            Some(DiscoveredServer {
+                url: url_for(ip, file.port),
+                port: file.port,
+                pid: Some(file.pid),
+                version: file.version,
+                directory: file.directory,
+                source: DiscoverySource::File,
+                status: HealthStatus::Unreachable {
+                    message: "Not checked".to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+fn from_sockets() -> Vec<DiscoveredServer> {
+    let listeners = match listeners::get_all() {
+        Ok(listeners) => listeners,
+        Err(e) => {
+            tracing::debug!("Failed to list listening sockets: {e}");
+            return vec![];
+        }
+    };
+
+    listeners
+        .into_iter()
+        .filter(|l| l.process.name.to_lowercase().starts_with("opencode"))
+        .filter(|l| l.socket.ip().is_loopback() || l.socket.ip().is_unspecified())
+        .map(|l| DiscoveredServer {
+            url: url_for(l.socket.ip(), l.socket.port()),
+            port: l.socket.port(),
+            pid: Some(l.process.pid),
+            version: None,
+            directory: None,
+            source: DiscoverySource::Socket,
+            status: HealthStatus::Unreachable {
+                message: "Not checked".to_string(),
+            },
+        })
+        .collect()
+}
+
+/// Returns servers that answered the health check (possibly asking for credentials), excluding
+/// this app's own sidecar and bridges.
+#[tauri::command]
+#[specta::specta]
+pub async fn discover_servers(app: AppHandle) -> Vec<DiscoveredServer> {
+    // The bridges listen from this process, which is named OpenCode too
+    let own_pids = [
+        Some(std::process::id()),
+        app.try_state::<ServerState>()
+            .and_then(|state| state.child_pid()),
+    ];
+
+    // Listing sockets and processes reads through the whole process table
+    let found = tauri::async_runtime::spawn_blocking(|| {
+        let mut found = from_sockets();
+        found.extend(from_files());
+        found
+    })
+    .await
+    .unwrap_or_default();
+
+    // Discovery files carry more detail, so they win over sockets for the same port
+    let mut candidates = BTreeMap::new();
+    for server in found {
+        candidates.insert(server.port, server);
+    }
+    candidates.retain(|_, server| server.pid.is_none() || !own_pids.contains(&server.pid));
+
+    let servers = future::join_all(candidates.into_values().map(|mut server| async move {
+        server.status = server::probe_health(&server.url, None, None).await;
+        server
+    }))
+    .await;
+
+    let servers: Vec<_> = servers
+        .into_iter()
+        .filter(|server| {
+            matches!(
+                server.status,
+                HealthStatus::Healthy | HealthStatus::Unauthorized { .. }
+            )
+        })
+        .collect();
+
+    tracing::info!(count = servers.len(), "Discovered local servers");
+
+    servers
+}
+
+/// Makes a discovered server the default once it accepts `auth`, so it's used instead of a
+/// sidecar. The frontend relaunches the app afterwards to connect to it.
+#[tauri::command]
+#[specta::specta]
+pub async fn attach_server(
+    app: AppHandle,
+    url: String,
+    auth: Option<ServerAuth>,
+) -> Result<ServerProfile, CommandError> {
+    let port = reqwest::Url::parse(&url)
+        .map_err(|e| CommandError::invalid_url(&url, e))?
+        .port_or_known_default()
+        .unwrap_or_default();
+
+    // Checked now, as a server that can't be used would only fail on the next start
+    match server::probe_health(&url, auth.as_ref(), None).await {
+        HealthStatus::Healthy => {}
+        HealthStatus::Unauthorized { code } => {
+            return Err(CommandError::invalid_input(
+                "auth",
+                format!("The server rejected the credentials ({})", code),
+            ));
+        }
+        HealthStatus::Unhealthy { code } => {
+            return Err(CommandError::invalid_input(
+                "url",
+                format!("The server at {} responded with an error ({})", url, code),
+            ));
+        }
+        HealthStatus::Unreachable { message } | HealthStatus::UntrustedCertificate { message } => {
+            return Err(CommandError::invalid_input(
+                "url",
+                format!("Could not connect to {}: {}", url, message),
+            ));
+        }
+    }
+
+    let profile = profiles::attach(&app, &format!("Local server :{port}"), &url, auth)?;
+
+    tracing::info!(url = %profile.url, "Attached to local server");
+
+    Ok(profile)
+}
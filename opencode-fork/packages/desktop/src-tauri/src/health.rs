@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Manager, State, Webview, ipc::Channel};
+use tauri_plugin_store::StoreExt;
+use tokio::{sync::watch, time::timeout};
+
+use crate::{
+    command_error::CommandError,
+    constants::{HEALTH_CHECK_INTERVAL_KEY, SETTINGS_STORE},
+    profiles::ServerAuth,
+    server::{self, HealthStatus},
+    subscriptions::Subscriptions,
+};
+
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+// Failures in a row before the server is reported as disconnected rather than degraded.
+const DISCONNECTED_AFTER: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    Degraded,
+    Disconnected,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerHealth {
+    pub state: ConnectionState,
+    pub status: HealthStatus,
+    /// Round trip of the last check, if the server answered at all.
+    pub latency_ms: Option<u32>,
+    pub consecutive_failures: u32,
+}
+
+pub struct HealthMonitorState {
+    health: watch::Sender<Option<ServerHealth>>,
+    interval: watch::Sender<Duration>,
+}
+
+impl HealthMonitorState {
+    pub fn new(app: &AppHandle) -> Self {
+        Self {
+            health: watch::Sender::new(None),
+            interval: watch::Sender::new(read_interval(app)),
+        }
+    }
+}
+
+fn read_interval(app: &AppHandle) -> Duration {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(HEALTH_CHECK_INTERVAL_KEY))
+        .and_then(|v| v.as_u64())
+        .map(Duration::from_millis)
+        .map(|interval| interval.max(MIN_INTERVAL))
+        .unwrap_or(DEFAULT_INTERVAL)
+}
+
+/// Polls the active server until the app exits, publishing every result.
+pub fn spawn_monitor(app: AppHandle, url: String, auth: Option<ServerAuth>) {
+    let state = app.state::<HealthMonitorState>();
+    let health = state.health.clone();
+    let mut interval = state.interval.subscribe();
+
+    tracing::info!(%url, "Starting server health monitor");
+
+    tokio::spawn(async move {
+        let mut consecutive_failures = 0;
+        let mut last_state = ConnectionState::Connected;
+
+        loop {
+            let started = Instant::now();
+            let status = server::probe_health(&url, auth.as_ref(), None).await;
+            let elapsed = started.elapsed();
+
+            let latency_ms = match status {
+                HealthStatus::Unreachable { .. } | HealthStatus::UntrustedCertificate { .. } => {
+                    None
+                }
+                _ => Some(u32::try_from(elapsed.as_millis()).unwrap_or(u32::MAX)),
+            };
+
+            if matches!(status, HealthStatus::Healthy) {
+                consecutive_failures = 0;
+            } else {
+                consecutive_failures += 1;
+            }
+
+            let state = match consecutive_failures {
+                0 => ConnectionState::Connected,
+                n if n < DISCONNECTED_AFTER => ConnectionState::Degraded,
+                _ => ConnectionState::Disconnected,
+            };
+
+            if state != last_state {
+                match state {
+                    ConnectionState::Connected => {
+                        tracing::info!(%url, "Server connection restored")
+                    }
+                    _ => tracing::warn!(
+                        %url,
+                        ?state,
+                        consecutive_failures,
+                        ?status,
+                        "Server health changed"
+                    ),
+                }
+                last_state = state;
+            }
+
+            health.send_replace(Some(ServerHealth {
+                state,
+                status,
+                latency_ms,
+                consecutive_failures,
+            }));
+
+            // Wake early when the interval changes so a shorter one applies right away
+            let period = *interval.borrow_and_update();
+            let _ = timeout(period, interval.changed()).await;
+        }
+    });
+}
+
+/// Sends the server's health to `events` now and whenever it changes, until stopped with
+/// `unsubscribe` and the returned id.
+#[tauri::command]
+#[specta::specta]
+pub async fn watch_server_health(
+    webview: Webview,
+    state: State<'_, HealthMonitorState>,
+    subscriptions: State<'_, Subscriptions>,
+    events: Channel<ServerHealth>,
+) -> Result<u32, CommandError> {
+    let mut rx = state.health.subscribe();
+
+    Ok(subscriptions.spawn(&webview, async move {
+        loop {
+            let health = rx.borrow_and_update().clone();
+
+            if let Some(health) = health
+                && events.send(health).is_err()
+            {
+                break;
+            }
+
+            if rx.changed().await.is_err() {
+                break;
+            }
+        }
+    }))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_health_check_interval(state: State<'_, HealthMonitorState>) -> u32 {
+    u32::try_from(state.interval.borrow().as_millis()).unwrap_or(u32::MAX)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_health_check_interval(
+    app: AppHandle,
+    state: State<'_, HealthMonitorState>,
+    interval_ms: u32,
+) -> Result<(), CommandError> {
+    let interval = Duration::from_millis(interval_ms.into());
+    if interval < MIN_INTERVAL {
+        return Err(CommandError::invalid_input(
+            "intervalMs",
+            format!(
+                "Health check interval must be at least {}ms",
+                MIN_INTERVAL.as_millis()
+            ),
+        ));
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(HEALTH_CHECK_INTERVAL_KEY, serde_json::json!(interval_ms));
+    store.save().map_err(CommandError::settings_save)?;
+
+    state.interval.send_replace(interval);
+
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! Startup phases reported to the loading window.
+//!
+//! Each step carries how long startup and the current phase have been running, so the window
+//! can point at whatever is slow. Phases are set as the app works through
+//! `setup_server_connection` and the first health check. Updates after startup has finished,
+//! such as health checks of a restarted sidecar, are ignored.
+
+use std::{
+    env,
+    path::PathBuf,
+    sync::{Mutex, PoisonError, TryLockError},
+    time::Instant,
+};
+
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+
+use crate::server::HEALTH_CHECK_TIMEOUT;
+
+/// Builds with SQLite storage create the database while migrating on first start.
+pub const SQLITE_ENABLED: bool = option_env!("OPENCODE_SQLITE").is_some();
+
+#[derive(Clone, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InitPhase {
+    /// Looking up the default server profile and the CLI config.
+    ResolvingConfig,
+    /// Checking the health of a configured server.
+    ConnectingRemote {
+        url: String,
+    },
+    /// Running the login shell to capture its environment.
+    ResolvingShellEnv,
+    Spawning,
+    /// Waiting for the sidecar to answer its health endpoint.
+    HealthChecking {
+        attempt: u32,
+    },
+    /// The sidecar is migrating its database. Progress is known once it reports a migration.
+    Migrating {
+        current: Option<u32>,
+        total: Option<u32>,
+    },
+    Done,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InitStep {
+    pub phase: InitPhase,
+    /// Time since startup began.
+    pub elapsed_ms: u32,
+    /// Time spent in the current phase.
+    pub phase_elapsed_ms: u32,
+}
+
+pub struct InitState {
+    started: Instant,
+    tx: watch::Sender<(InitPhase, Instant)>,
+    // Copy of the current phase that can be read without waiting, see `phase`
+    current: Mutex<InitPhase>,
+    // Last time the sidecar reported migration progress
+    migration_activity: Mutex<Option<Instant>>,
+}
+
+impl Default for InitState {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            tx: watch::channel((InitPhase::ResolvingConfig, now)).0,
+            current: Mutex::new(InitPhase::ResolvingConfig),
+            migration_activity: Mutex::new(None),
+        }
+    }
+}
+
+impl InitState {
+    pub fn subscribe(&self) -> watch::Receiver<(InitPhase, Instant)> {
+        self.tx.subscribe()
+    }
+
+    pub fn step(&self, (phase, since): &(InitPhase, Instant)) -> InitStep {
+        let ms = |since: Instant| u32::try_from(since.elapsed().as_millis()).unwrap_or(u32::MAX);
+        InitStep {
+            phase: phase.clone(),
+            elapsed_ms: ms(self.started),
+            phase_elapsed_ms: ms(*since),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&InitPhase) -> Option<InitPhase>) {
+        self.tx.send_if_modified(|(current, since)| {
+            if *current == InitPhase::Done {
+                return false;
+            }
+            let Some(next) = f(current) else {
+                return false;
+            };
+
+            // Progress within a phase keeps its start time
+            if std::mem::discriminant(current) != std::mem::discriminant(&next) {
+                *since = Instant::now();
+                tracing::debug!(phase = ?next, elapsed = ?self.started.elapsed(), "Startup phase");
+            }
+            *self.current.lock().unwrap_or_else(PoisonError::into_inner) = next.clone();
+            *current = next;
+            true
+        });
+    }
+}
+
+pub fn set_phase(app: &AppHandle, phase: InitPhase) {
+    if let Some(state) = app.try_state::<InitState>() {
+        state.update(|_| Some(phase));
+    }
+}
+
+/// The phase startup is in, `Done` once it has finished. Doesn't wait, so it's unset while
+/// the phase is being changed, and it's safe to call from the panic hook.
+pub fn phase(app: &AppHandle) -> Option<InitPhase> {
+    let state = app.try_state::<InitState>()?;
+    let phase = match state.current.try_lock() {
+        Ok(phase) => phase,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return None,
+    };
+
+    Some(phase.clone())
+}
+
+/// Records a health check of the sidecar. Migrations are reported over health checks, since
+/// the sidecar only answers once they're done.
+pub fn health_attempt(app: &AppHandle, attempt: u32) {
+    if let Some(state) = app.try_state::<InitState>() {
+        state.update(|current| {
+            (!matches!(current, InitPhase::Migrating { .. }))
+                .then_some(InitPhase::HealthChecking { attempt })
+        });
+    }
+}
+
+/// Starts the line the sidecar prints for the desktop app when it runs a migration, whatever
+/// its log level.
+const MIGRATION_MARKER: &str = "opencode-migration ";
+
+/// Parses a migration progress line from the sidecar, like `opencode-migration index=2 total=5`,
+/// or its log line at INFO and below, like
+/// `INFO  2025-01-01T00:00:00 +3ms service=storage index=2 total=5 running migration`.
+pub fn parse_migration(line: &str) -> Option<(u32, u32)> {
+    if !line.starts_with(MIGRATION_MARKER) && !line.contains("running migration") {
+        return None;
+    }
+
+    let field = |name: &str| {
+        line.split_whitespace().find_map(|token| {
+            token
+                .strip_prefix(name)?
+                .strip_prefix('=')?
+                .parse::<u32>()
+                .ok()
+        })
+    };
+
+    Some((field("index")? + 1, field("total")?))
+}
+
+pub fn migration_progress(app: &AppHandle, current: u32, total: u32) {
+    if let Some(state) = app.try_state::<InitState>() {
+        *state.migration_activity.lock().unwrap() = Some(Instant::now());
+        state.update(|_| {
+            Some(InitPhase::Migrating {
+                current: Some(current),
+                total: Some(total),
+            })
+        });
+    }
+}
+
+/// Whether the sidecar is still migrating, in which case the health check deadline is
+/// extended. That's the case while its database doesn't exist yet, or while it keeps
+/// reporting progress.
+pub fn is_migrating(app: &AppHandle) -> bool {
+    let Some(state) = app.try_state::<InitState>() else {
+        return false;
+    };
+    if !matches!(state.tx.borrow().0, InitPhase::Migrating { .. }) {
+        return false;
+    }
+
+    let recent = state
+        .migration_activity
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() < HEALTH_CHECK_TIMEOUT);
+
+    recent || (SQLITE_ENABLED && !sqlite_file_exists())
+}
+
+pub fn sqlite_file_exists() -> bool {
+    let Ok(path) = opencode_db_path() else {
+        return true;
+    };
+
+    path.exists()
+}
+
+pub fn opencode_db_path() -> Result<PathBuf, &'static str> {
+    let xdg_data_home = env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty());
+
+    let data_home = match xdg_data_home {
+        Some(v) => PathBuf::from(v),
+        None => {
+            let home = dirs::home_dir().ok_or("cannot determine home directory")?;
+            home.join(".local").join("share")
+        }
+    };
+
+    Ok(data_home.join("opencode").join("opencode.db"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_migration_progress() {
+        let cases = [
+            ("opencode-migration index=0 total=5\n", Some((1, 5))),
+            ("opencode-migration index=4 total=5", Some((5, 5))),
+            (
+                "INFO  2025-06-01T12:00:00 +3ms service=storage index=2 total=5 running migration\n",
+                Some((3, 5)),
+            ),
+            (
+                "WARN  2025-06-01T12:00:00 +0ms service=storage index=0 total=1 running migration",
+                Some((1, 1)),
+            ),
+            // Other storage output
+            (
+                "ERROR 2025-06-01T12:00:01 +12ms service=storage index=2 failed to run migration",
+                None,
+            ),
+            (
+                "INFO  2025-06-01T12:00:00 +1ms service=server method=GET path=/global/health request",
+                None,
+            ),
+            ("opencode-migration total=5", None),
+            ("", None),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(parse_migration(line), expected, "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    command_error::CommandError,
+    constants::{SETTINGS_STORE, SIDECAR_LAUNCH_OPTIONS_KEY},
+};
+
+// The options the running sidecar was started with
+static APPLIED: Mutex<Option<SidecarLaunchOptions>> = Mutex::new(None);
+
+/// Set by the app itself, so they can't be overridden.
+const RESERVED_ENV: &[&str] = &[
+    "OPENCODE_CLIENT",
+    "OPENCODE_SERVER_PASSWORD",
+    "OPENCODE_SERVER_USERNAME",
+    "XDG_STATE_HOME",
+];
+
+/// Decided by the app when it spawns the sidecar.
+const RESERVED_ARGS: &[&str] = &[
+    "--hostname",
+    "--log-level",
+    "--port",
+    "--print-logs",
+    "--unix-socket",
+];
+
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, specta::Type, Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarLogLevel {
+    Debug,
+    Info,
+    #[default]
+    Warn,
+    Error,
+}
+
+impl SidecarLogLevel {
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+        }
+    }
+
+    /// The level of a line of sidecar output, as printed by its logger.
+    pub fn from_arg(level: &str) -> Option<Self> {
+        match level {
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+/// How the sidecar is launched. Changes apply the next time it starts, see
+/// [`restart_sidecar`](crate::server::restart_sidecar).
+#[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SidecarLaunchOptions {
+    pub log_level: SidecarLogLevel,
+    /// `OPENCODE_EXPERIMENTAL_ICON_DISCOVERY`
+    pub icon_discovery: bool,
+    /// `OPENCODE_EXPERIMENTAL_FILEWATCHER`
+    pub file_watcher: bool,
+    /// Extra environment variables, such as provider endpoints or feature flags.
+    pub env: BTreeMap<String, String>,
+    /// Extra arguments passed to `opencode serve`.
+    pub args: Vec<String>,
+}
+
+impl Default for SidecarLaunchOptions {
+    fn default() -> Self {
+        Self {
+            log_level: SidecarLogLevel::default(),
+            icon_discovery: true,
+            file_watcher: true,
+            env: BTreeMap::new(),
+            args: vec![],
+        }
+    }
+}
+
+impl SidecarLaunchOptions {
+    /// The experimental flags as environment variables.
+    pub fn flag_env(&self) -> [(&'static str, &'static str); 2] {
+        let flag = |enabled: bool| if enabled { "true" } else { "false" };
+        [
+            (
+                "OPENCODE_EXPERIMENTAL_ICON_DISCOVERY",
+                flag(self.icon_discovery),
+            ),
+            ("OPENCODE_EXPERIMENTAL_FILEWATCHER", flag(self.file_watcher)),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), CommandError> {
+        let env = |message: String| Err(CommandError::invalid_input("env", message));
+        let args = |message: String| Err(CommandError::invalid_input("args", message));
+
+        for (name, value) in &self.env {
+            let mut chars = name.chars();
+            let valid = chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return env(format!("Invalid environment variable name '{}'", name));
+            }
+            if RESERVED_ENV.contains(&name.as_str()) {
+                return env(format!("{} is set by the app and can't be changed", name));
+            }
+            if self.flag_env().iter().any(|(flag, _)| flag == name) {
+                return env(format!(
+                    "{} is set by its toggle in the launch options",
+                    name
+                ));
+            }
+            if value.contains('\0') {
+                return env(format!("Value of {} contains a null byte", name));
+            }
+        }
+
+        for arg in &self.args {
+            if arg.is_empty() {
+                return args("Arguments can't be empty".to_string());
+            }
+            if arg.contains('\0') {
+                return args(format!("Argument '{}' contains a null byte", arg));
+            }
+            let flag = arg.split('=').next().unwrap_or(arg);
+            if RESERVED_ARGS.contains(&flag) {
+                return args(format!("{} is set by the app and can't be changed", flag));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Records the options a sidecar is being started with.
+pub fn mark_applied(options: &SidecarLaunchOptions) {
+    *APPLIED.lock().unwrap() = Some(options.clone());
+}
+
+pub fn read(app: &AppHandle) -> SidecarLaunchOptions {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SIDECAR_LAUNCH_OPTIONS_KEY))
+        .and_then(|v| {
+            serde_json::from_value(v)
+                .inspect_err(|e| tracing::warn!("Invalid sidecar launch options: {e}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_launch_options(app: AppHandle) -> SidecarLaunchOptions {
+    read(&app)
+}
+
+/// Saves the options, returning whether they differ from the ones the running sidecar was
+/// started with and a restart is needed to apply them.
+#[tauri::command]
+#[specta::specta]
+pub fn set_sidecar_launch_options(
+    app: AppHandle,
+    options: SidecarLaunchOptions,
+) -> Result<bool, CommandError> {
+    options.validate()?;
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(SIDECAR_LAUNCH_OPTIONS_KEY, serde_json::json!(options));
+    store.save().map_err(CommandError::settings_save)?;
+
+    Ok(APPLIED
+        .lock()
+        .unwrap()
+        .as_ref()
+        .is_some_and(|applied| *applied != options))
+}
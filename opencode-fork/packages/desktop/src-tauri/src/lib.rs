@@ -1,12 +1,35 @@
+mod bridge;
 mod cli;
+mod command_error;
 mod constants;
+mod crash_report;
+mod diagnostics;
+mod discovery;
+mod health;
+mod init;
 #[cfg(windows)]
 mod job_object;
+mod launch_options;
 #[cfg(target_os = "linux")]
 pub mod linux_display;
+mod log_browser;
+mod log_levels;
 mod logging;
 mod markdown;
+mod ports;
+#[cfg(target_os = "linux")]
+mod process_group;
+mod profiles;
+mod redact;
+mod resource_limits;
 mod server;
+pub mod shell_env;
+mod sidecar_log;
+mod sidecar_registry;
+mod startup_error;
+mod subscriptions;
+mod tls;
+mod transport;
 mod window_customizer;
 mod windows;
 
@@ -17,92 +40,111 @@ use futures::{
 #[cfg(windows)]
 use job_object::*;
 use std::{
-    env,
-    net::TcpListener,
-    path::PathBuf,
     process::Command,
-    sync::{Arc, Mutex},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
     time::Duration,
 };
-use tauri::{AppHandle, Manager, RunEvent, State, ipc::Channel};
+use tauri::{AppHandle, Manager, RunEvent, State, ipc::Channel, webview::PageLoadEvent};
 #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
 use tauri_plugin_deep_link::DeepLinkExt;
-use tauri_plugin_shell::process::CommandChild;
 use tokio::{
-    sync::{oneshot, watch},
+    sync::{Notify, oneshot},
     time::{sleep, timeout},
 };
 
-use crate::cli::sync_cli;
+use crate::cli::{SidecarChild, sync_cli};
+use crate::command_error::CommandError;
 use crate::constants::*;
-use crate::server::get_saved_server_url;
+use crate::init::{InitPhase, InitState, InitStep};
+use crate::ports::PortSource;
+use crate::profiles::ServerAuth;
+use crate::server::{HealthStatus, ServerPhase, get_saved_server};
+use crate::startup_error::{StartupError, StartupErrorReason};
+use crate::transport::SidecarAddr;
 use crate::windows::{LoadingWindow, MainWindow};
 
 #[derive(Clone, serde::Serialize, specta::Type, Debug)]
 struct ServerReadyData {
     url: String,
-    password: Option<String>,
-}
-
-#[derive(Clone, Copy, serde::Serialize, specta::Type, Debug)]
-#[serde(tag = "phase", rename_all = "snake_case")]
-enum InitStep {
-    ServerWaiting,
-    SqliteWaiting,
-    Done,
-}
-
-struct InitState {
-    current: watch::Receiver<InitStep>,
+    auth: Option<ServerAuth>,
 }
 
 #[derive(Clone)]
 struct ServerState {
-    child: Arc<Mutex<Option<CommandChild>>>,
-    status: future::Shared<oneshot::Receiver<Result<ServerReadyData, String>>>,
+    child: Arc<Mutex<Option<SidecarChild>>>,
+    status: future::Shared<oneshot::Receiver<Result<ServerReadyData, StartupError>>>,
+    // Set once the sidecar is being shut down on purpose, so the supervisor doesn't restart it
+    stopping: Arc<AtomicBool>,
+    // Set while the sidecar is being stopped so the supervisor restarts it right away
+    restart_requested: Arc<AtomicBool>,
+    // Signalled by the supervisor once a sidecar that was asked to stop has exited
+    stopped: Arc<Notify>,
+    // Where the running sidecar can be reached, used to ask it to dispose before shutdown
+    endpoint: Arc<Mutex<Option<ServerReadyData>>>,
 }
 
 impl ServerState {
     pub fn new(
-        child: Option<CommandChild>,
-        status: Shared<oneshot::Receiver<Result<ServerReadyData, String>>>,
+        child: Option<SidecarChild>,
+        status: Shared<oneshot::Receiver<Result<ServerReadyData, StartupError>>>,
     ) -> Self {
         Self {
             child: Arc::new(Mutex::new(child)),
             status,
+            stopping: Arc::new(AtomicBool::new(false)),
+            restart_requested: Arc::new(AtomicBool::new(false)),
+            stopped: Arc::new(Notify::new()),
+            endpoint: Arc::new(Mutex::new(None)),
         }
     }
 
-    pub fn set_child(&self, child: Option<CommandChild>) {
+    pub fn set_child(&self, child: Option<SidecarChild>) {
         *self.child.lock().unwrap() = child;
     }
-}
 
-#[tauri::command]
-#[specta::specta]
-fn kill_sidecar(app: AppHandle) {
-    let Some(server_state) = app.try_state::<ServerState>() else {
-        tracing::info!("Server not running");
-        return;
-    };
+    pub fn take_child(&self) -> Option<SidecarChild> {
+        self.child
+            .lock()
+            .expect("Failed to acquire mutex lock")
+            .take()
+    }
 
-    let Some(server_state) = server_state
-        .child
-        .lock()
-        .expect("Failed to acquire mutex lock")
-        .take()
-    else {
-        tracing::info!("Server state missing");
-        return;
-    };
+    pub fn child_pid(&self) -> Option<u32> {
+        self.child.lock().unwrap().as_ref().map(|child| child.pid())
+    }
+
+    pub fn set_endpoint(&self, endpoint: ServerReadyData) {
+        *self.endpoint.lock().unwrap() = Some(endpoint);
+    }
 
-    let _ = server_state.kill();
+    pub fn endpoint(&self) -> Option<ServerReadyData> {
+        self.endpoint.lock().unwrap().clone()
+    }
+
+    pub fn set_stopping(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
 
-    tracing::info!("Killed server");
+    pub fn request_restart(&self) {
+        self.restart_requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn take_restart_request(&self) -> bool {
+        self.restart_requested.swap(false, Ordering::SeqCst)
+    }
 }
 
-fn get_logs() -> String {
-    logging::tail()
+#[tauri::command]
+#[specta::specta]
+async fn kill_sidecar(app: AppHandle) {
+    server::shutdown(&app).await;
 }
 
 #[tauri::command]
@@ -111,19 +153,30 @@ async fn await_initialization(
     state: State<'_, ServerState>,
     init_state: State<'_, InitState>,
     events: Channel<InitStep>,
-) -> Result<ServerReadyData, String> {
-    let mut rx = init_state.current.clone();
+) -> Result<ServerReadyData, StartupError> {
+    let mut rx = init_state.subscribe();
 
     let events = async {
-        let e = (*rx.borrow()).clone();
-        let _ = events.send(e).unwrap();
-
-        while rx.changed().await.is_ok() {
-            let step = *rx.borrow_and_update();
+        let step = init_state.step(&rx.borrow_and_update());
+        let _ = events.send(step);
+
+        // Resend the current phase every second so its elapsed time stays current
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            tokio::select! {
+                changed = rx.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                _ = ticker.tick() => {}
+            }
 
+            let step = init_state.step(&rx.borrow_and_update());
+            let done = step.phase == InitPhase::Done;
             let _ = events.send(step);
 
-            if matches!(step, InitStep::Done) {
+            if done {
                 break;
             }
         }
@@ -132,7 +185,7 @@ async fn await_initialization(
     future::join(state.status.clone(), events)
         .await
         .0
-        .map_err(|_| "Failed to get server status".to_string())?
+        .map_err(|_| StartupError::spawn_failed("Failed to get server status"))?
 }
 
 #[tauri::command]
@@ -604,7 +657,7 @@ fn get_display_backend() -> Option<LinuxDisplayBackend> {
 
 #[tauri::command]
 #[specta::specta]
-fn set_display_backend(_app: AppHandle, _backend: LinuxDisplayBackend) -> Result<(), String> {
+fn set_display_backend(_app: AppHandle, _backend: LinuxDisplayBackend) -> Result<(), CommandError> {
     #[cfg(target_os = "linux")]
     {
         let prefer = matches!(_backend, LinuxDisplayBackend::Wayland);
@@ -626,17 +679,62 @@ pub fn run() {
         // Then register them (separated by a comma)
         .commands(tauri_specta::collect_commands![
             kill_sidecar,
+            server::restart_sidecar,
+            server::get_shutdown_grace_period,
+            server::set_shutdown_grace_period,
             cli::install_cli,
             await_initialization,
             server::get_default_server_url,
             server::set_default_server_url,
+            profiles::list_server_profiles,
+            profiles::create_server_profile,
+            profiles::update_server_profile,
+            profiles::delete_server_profile,
+            profiles::test_server_profile,
+            discovery::discover_servers,
+            discovery::attach_server,
+            health::watch_server_health,
+            subscriptions::unsubscribe,
+            health::get_health_check_interval,
+            health::set_health_check_interval,
+            ports::get_sidecar_port_setting,
+            ports::set_sidecar_port_setting,
+            resource_limits::get_sidecar_resource_limits,
+            resource_limits::set_sidecar_resource_limits,
+            resource_limits::get_sidecar_resource_usage,
+            launch_options::get_sidecar_launch_options,
+            launch_options::set_sidecar_launch_options,
+            shell_env::get_shell_env_diagnostics,
+            shell_env::refresh_shell_env,
+            sidecar_log::get_sidecar_output,
+            log_levels::get_log_levels,
+            log_levels::set_desktop_log_filter,
+            log_levels::set_sidecar_log_level,
+            log_levels::start_debug_logging,
+            log_levels::stop_debug_logging,
+            log_browser::list_log_files,
+            log_browser::read_log_file,
+            log_browser::watch_log_file,
+            log_browser::tail_log_file,
+            logging::get_log_retention,
+            logging::set_log_retention,
+            diagnostics::export_diagnostics,
+            crash_report::list_crash_reports,
+            crash_report::read_crash_report,
+            crash_report::save_crash_report,
+            crash_report::delete_crash_report,
             get_display_backend,
             set_display_backend,
             markdown::parse_markdown_command,
             check_app_exists,
             resolve_app_path
         ])
-        .events(tauri_specta::collect_events![LoadingWindowComplete])
+        .events(tauri_specta::collect_events![
+            LoadingWindowComplete,
+            server::ServerStatusChanged,
+            server::ShutdownProgress,
+            resource_limits::ResourceLimitHit
+        ])
         .error_handling(tauri_specta::ErrorHandlingMode::Throw);
 
     #[cfg(debug_assertions)] // <- Only export on non-release builds
@@ -647,11 +745,6 @@ pub fn run() {
         )
         .expect("Failed to export typescript bindings");
 
-    #[cfg(all(target_os = "macos", not(debug_assertions)))]
-    let _ = std::process::Command::new("killall")
-        .arg("opencode-cli")
-        .output();
-
     let mut builder = tauri::Builder::default()
         .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
             // Focus existing window when another instance is launched
@@ -679,6 +772,14 @@ pub fn run() {
         .plugin(crate::window_customizer::PinchZoomDisablePlugin)
         .plugin(tauri_plugin_decorum::init())
         .invoke_handler(builder.invoke_handler())
+        .on_page_load(|webview, payload| {
+            // Channels of the previous page can't be sent to anymore
+            if payload.event() == PageLoadEvent::Started
+                && let Some(subscriptions) = webview.try_state::<subscriptions::Subscriptions>()
+            {
+                subscriptions.stop_webview(webview.label());
+            }
+        })
         .setup(move |app| {
             let handle = app.handle().clone();
 
@@ -688,7 +789,12 @@ pub fn run() {
                 .expect("failed to resolve app log dir");
             // Hold the guard in managed state so it lives for the app's lifetime,
             // ensuring all buffered logs are flushed on shutdown.
-            handle.manage(logging::init(&log_dir));
+            let retention = logging::read_retention(&handle);
+            handle.manage(logging::init(&log_dir, retention));
+            sidecar_log::init(&log_dir, retention);
+            crash_report::install(&handle);
+
+            sidecar_registry::reap_orphans(&handle);
 
             builder.mount_events(&handle);
             tauri::async_runtime::spawn(initialize(handle));
@@ -707,7 +813,8 @@ pub fn run() {
             if let RunEvent::Exit = event {
                 tracing::info!("Received Exit");
 
-                kill_sidecar(app.clone());
+                // Block so the sidecar gets its grace period before the process goes away
+                tauri::async_runtime::block_on(server::shutdown(app));
             }
         });
 }
@@ -718,9 +825,7 @@ struct LoadingWindowComplete;
 async fn initialize(app: AppHandle) {
     tracing::info!("Initializing app");
 
-    let (init_tx, init_rx) = watch::channel(InitStep::ServerWaiting);
-
-    setup_app(&app, init_rx);
+    setup_app(&app);
     spawn_cli_sync_task(app.clone());
 
     let (server_ready_tx, server_ready_rx) = oneshot::channel();
@@ -731,15 +836,10 @@ async fn initialize(app: AppHandle) {
 
     tracing::info!("Main and loading windows created");
 
-    let sqlite_enabled = option_env!("OPENCODE_SQLITE").is_some();
-
     let loading_task = tokio::spawn({
-        let init_tx = init_tx.clone();
         let app = app.clone();
 
         async move {
-            let mut sqlite_exists = sqlite_file_exists();
-
             tracing::info!("Setting up server connection");
             let server_connection = setup_server_connection(app.clone()).await;
 
@@ -748,30 +848,35 @@ async fn initialize(app: AppHandle) {
                 ServerConnection::CLI {
                     child,
                     health_check,
-                    url,
+                    hostname,
+                    addr,
                     password,
+                    ports,
                 } => {
                     let app = app.clone();
                     Some(
                         async move {
-                            let res = timeout(Duration::from_secs(30), health_check.0).await;
-                            let err = match res {
-                                Ok(Ok(Ok(()))) => None,
-                                Ok(Ok(Err(e))) => Some(e),
-                                Ok(Err(e)) => Some(format!("Health check task failed: {e}")),
-                                Err(_) => Some("Health check timed out".to_string()),
+                            let res = server::await_local_server(
+                                app.clone(),
+                                hostname,
+                                password.clone(),
+                                ports,
+                                (child, health_check, addr),
+                            )
+                            .await;
+
+                            let server::LocalServer { child, exit, addr } = match res {
+                                Ok(server) => server,
+                                Err(err) => {
+                                    server::emit_status(&app, ServerPhase::Failed, 0, None);
+
+                                    tracing::error!("Failed to spawn OpenCode Server: {err}");
+                                    return Err(err);
+                                }
                             };
+                            let url = addr.url();
 
-                            if let Some(err) = err {
-                                let _ = child.kill();
-
-                                return Err(format!(
-                                    "Failed to spawn OpenCode Server ({err}). Logs:\n{}",
-                                    get_logs()
-                                ));
-                            }
-
-                            tracing::info!("CLI health check OK");
+                            tracing::info!(?addr, "CLI health check OK");
 
                             #[cfg(windows)]
                             {
@@ -779,51 +884,68 @@ async fn initialize(app: AppHandle) {
                                 job_state.assign_pid(child.pid());
                             }
 
-                            app.state::<ServerState>().set_child(Some(child));
+                            let state = app.state::<ServerState>();
+                            state.set_child(Some(child));
+                            state.set_endpoint(ServerReadyData {
+                                url: url.clone(),
+                                auth: Some(server::sidecar_auth(&password)),
+                            });
 
-                            Ok(ServerReadyData { url, password })
+                            server::emit_status(&app, ServerPhase::Running, 0, None);
+                            server::supervise(app.clone(), exit, addr, password.clone());
+
+                            Ok(ServerReadyData {
+                                url,
+                                auth: Some(server::sidecar_auth(&password)),
+                            })
                         }
                         .map(move |res| {
                             let _ = server_ready_tx.send(res);
                         }),
                     )
                 }
-                ServerConnection::Existing { url } => {
+                ServerConnection::Existing { url, auth } => {
                     let _ = server_ready_tx.send(Ok(ServerReadyData {
                         url: url.to_string(),
-                        password: None,
+                        auth,
                     }));
                     None
                 }
+                ServerConnection::Failed(err) => {
+                    server::emit_status(&app, ServerPhase::Failed, 0, None);
+                    let _ = server_ready_tx.send(Err(err));
+                    None
+                }
             };
 
             if let Some(cli_health_check) = cli_health_check {
-                if sqlite_enabled {
-                    tracing::debug!(sqlite_exists, "Checking sqlite file existence");
-                    if !sqlite_exists {
-                        tracing::info!(
-                            path = %opencode_db_path().expect("failed to get db path").display(),
-                            "Sqlite file not found, waiting for it to be generated"
-                        );
-                        let _ = init_tx.send(InitStep::SqliteWaiting);
-
-                        while !sqlite_exists {
-                            sleep(Duration::from_secs(1)).await;
-                            sqlite_exists = sqlite_file_exists();
-                        }
-                    }
+                // The sidecar creates the database by migrating, which can take minutes
+                if init::SQLITE_ENABLED && !init::sqlite_file_exists() {
+                    tracing::info!(
+                        path = %init::opencode_db_path().expect("failed to get db path").display(),
+                        "Sqlite file not found, waiting for it to be generated"
+                    );
+                    init::set_phase(
+                        &app,
+                        InitPhase::Migrating {
+                            current: None,
+                            total: None,
+                        },
+                    );
                 }
 
                 tokio::spawn(cli_health_check);
             }
 
-            let _ = server_ready_rx.await;
+            if let Ok(Ok(ready)) = server_ready_rx.await {
+                health::spawn_monitor(app, ready.url, ready.auth);
+            }
         }
     })
     .map_err(|_| ())
     .shared();
 
-    let loading_window = if sqlite_enabled
+    let loading_window = if init::SQLITE_ENABLED
         && timeout(Duration::from_secs(1), loading_task.clone())
             .await
             .is_err()
@@ -843,7 +965,7 @@ async fn initialize(app: AppHandle) {
 
     tracing::info!("Loading done, completing initialisation");
 
-    let _ = init_tx.send(InitStep::Done);
+    init::set_phase(&app, InitPhase::Done);
 
     if loading_window.is_some() {
         loading_window_complete.await;
@@ -858,14 +980,17 @@ async fn initialize(app: AppHandle) {
     }
 }
 
-fn setup_app(app: &tauri::AppHandle, init_rx: watch::Receiver<InitStep>) {
+fn setup_app(app: &tauri::AppHandle) {
     #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
     app.deep_link().register_all().ok();
 
     #[cfg(windows)]
     app.manage(JobObjectState::new());
 
-    app.manage(InitState { current: init_rx });
+    app.manage(InitState::default());
+    app.manage(health::HealthMonitorState::new(app));
+    app.manage(resource_limits::ResourceMonitorState::default());
+    app.manage(subscriptions::Subscriptions::default());
 }
 
 fn spawn_cli_sync_task(app: AppHandle) {
@@ -879,85 +1004,166 @@ fn spawn_cli_sync_task(app: AppHandle) {
 enum ServerConnection {
     Existing {
         url: String,
+        auth: Option<ServerAuth>,
     },
     CLI {
-        url: String,
-        password: Option<String>,
-        child: CommandChild,
+        hostname: String,
+        addr: SidecarAddr,
+        password: String,
+        ports: PortSource,
+        child: SidecarChild,
         health_check: server::HealthCheck,
     },
+    /// No sidecar could be started, e.g. because the configured port is taken.
+    Failed(StartupError),
 }
 
 async fn setup_server_connection(app: AppHandle) -> ServerConnection {
-    let custom_url = get_saved_server_url(&app).await;
-
-    tracing::info!(?custom_url, "Attempting server connection");
+    init::set_phase(&app, InitPhase::ResolvingConfig);
+    let profile = get_saved_server(&app).await;
+
+    tracing::info!(
+        profile = ?profile.as_ref().map(|p| &p.name),
+        "Attempting server connection"
+    );
+
+    if let Some(profile) = &profile {
+        init::set_phase(
+            &app,
+            InitPhase::ConnectingRemote {
+                url: profile.url.clone(),
+            },
+        );
+    }
 
-    if let Some(url) = custom_url
-        && server::check_health_or_ask_retry(&app, &url).await
+    if let Some(mut profile) = profile
+        && server::check_health_or_ask_retry(&app, &mut profile).await
     {
-        tracing::info!(%url, "Connected to custom server");
-        return ServerConnection::Existing { url: url.clone() };
+        tracing::info!(url = %profile.url, name = %profile.name, "Connected to custom server");
+
+        // The webview can't use custom TLS settings, and can't put a bearer token on the
+        // terminal's WebSocket, so both go through the bridge
+        let custom_tls = profile.tls.as_ref().is_some_and(|tls| tls.is_custom());
+        let bearer = matches!(profile.auth, Some(ServerAuth::Bearer { .. }));
+        if custom_tls || bearer {
+            match bridge::start(&profile).await {
+                Ok(bridge) => {
+                    return ServerConnection::Existing {
+                        url: bridge.url,
+                        auth: Some(server::sidecar_auth(&bridge.password)),
+                    };
+                }
+                Err(e) => {
+                    // The webview can't reach the server without it
+                    tracing::error!("Failed to start bridge: {e}");
+                    return ServerConnection::Failed(StartupError::new(
+                        StartupErrorReason::BridgeFailed {
+                            url: profile.url,
+                            message: e,
+                        },
+                    ));
+                }
+            }
+        }
+
+        return ServerConnection::Existing {
+            url: profile.url,
+            auth: profile.auth,
+        };
     }
 
-    let local_port = get_sidecar_port();
+    let ports = PortSource::resolve(&app);
     let hostname = "127.0.0.1";
-    let local_url = format!("http://{hostname}:{local_port}");
 
-    tracing::debug!(url = %local_url, "Checking health of local server");
-    if server::check_health(&local_url, None).await {
-        tracing::info!(url = %local_url, "Health check OK, using existing server");
-        return ServerConnection::Existing { url: local_url };
+    // A server already listening on a pinned port is reused rather than reported as a conflict
+    if let Some(port) = ports.pinned() {
+        let local_url = format!("http://{hostname}:{port}");
+
+        tracing::debug!(url = %local_url, "Checking health of local server");
+        match server::probe_health(&local_url, None, None).await {
+            HealthStatus::Healthy => {
+                tracing::info!(url = %local_url, "Health check OK, using existing server");
+                return ServerConnection::Existing {
+                    url: local_url,
+                    auth: None,
+                };
+            }
+            HealthStatus::Unauthorized { .. } => {
+                tracing::error!(url = %local_url, "Server on pinned port requires credentials");
+                return ServerConnection::Failed(StartupError::new(
+                    StartupErrorReason::AuthRejected { url: local_url },
+                ));
+            }
+            _ => {}
+        }
     }
 
     let password = uuid::Uuid::new_v4().to_string();
+    redact::register(&password);
+
+    let addr = match sidecar_addr(&app, &ports, hostname, &password).await {
+        Ok(addr) => addr,
+        Err(e) => {
+            tracing::error!("Failed to allocate a sidecar port: {e}");
+            return ServerConnection::Failed(StartupError::port_conflict(ports.pinned(), e));
+        }
+    };
+
+    // Already done while resolving the config, unless a profile made that unnecessary
+    #[cfg(unix)]
+    {
+        init::set_phase(&app, InitPhase::ResolvingShellEnv);
+        shell_env::warm().await;
+    }
 
     tracing::info!("Spawning new local server");
+    init::set_phase(&app, InitPhase::Spawning);
+    server::emit_status(&app, ServerPhase::Starting, 0, None);
     let (child, health_check) =
-        server::spawn_local_server(app, hostname.to_string(), local_port, password.clone());
+        match server::spawn_local_server(app, addr.clone(), password.clone()) {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                tracing::error!("Failed to spawn OpenCode Server: {e}");
+                return ServerConnection::Failed(e.into());
+            }
+        };
 
     ServerConnection::CLI {
-        url: local_url,
-        password: Some(password),
+        hostname: hostname.to_string(),
+        addr,
+        password,
+        ports,
         child,
         health_check,
     }
 }
 
-fn get_sidecar_port() -> u32 {
-    option_env!("OPENCODE_PORT")
-        .map(|s| s.to_string())
-        .or_else(|| std::env::var("OPENCODE_PORT").ok())
-        .and_then(|port_str| port_str.parse().ok())
-        .unwrap_or_else(|| {
-            TcpListener::bind("127.0.0.1:0")
-                .expect("Failed to bind to find free port")
-                .local_addr()
-                .expect("Failed to get local address")
-                .port()
-        }) as u32
-}
-
-fn sqlite_file_exists() -> bool {
-    let Ok(path) = opencode_db_path() else {
-        return true;
-    };
-
-    path.exists()
-}
-
-fn opencode_db_path() -> Result<PathBuf, &'static str> {
-    let xdg_data_home = env::var_os("XDG_DATA_HOME").filter(|v| !v.is_empty());
-
-    let data_home = match xdg_data_home {
-        Some(v) => PathBuf::from(v),
-        None => {
-            let home = dirs::home_dir().ok_or("cannot determine home directory")?;
-            home.join(".local").join("share")
+/// Prefers a private Unix socket for the sidecar, see [`transport`].
+#[cfg_attr(not(unix), allow(unused_variables))]
+async fn sidecar_addr(
+    app: &AppHandle,
+    ports: &PortSource,
+    hostname: &str,
+    password: &str,
+) -> Result<SidecarAddr, String> {
+    #[cfg(unix)]
+    if ports.is_auto() {
+        match transport::socket_path(app) {
+            Ok(socket) => match bridge::start_unix(&socket, password).await {
+                Ok(bridge) => {
+                    return Ok(SidecarAddr::Unix {
+                        socket,
+                        url: bridge.url,
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to start socket bridge, using TCP: {e}"),
+            },
+            Err(e) => tracing::warn!("No private socket directory, using TCP: {e}"),
         }
-    };
+    }
 
-    Ok(data_home.join("opencode").join("opencode.db"))
+    let port = ports.pick(&[])?;
+    Ok(SidecarAddr::tcp(hostname, port))
 }
 
 // Creates a `once` listener for the specified event and returns a future that resolves
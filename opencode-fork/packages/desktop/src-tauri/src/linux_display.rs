@@ -4,7 +4,7 @@ use std::path::PathBuf;
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 
-use crate::constants::SETTINGS_STORE;
+use crate::{command_error::CommandError, constants::SETTINGS_STORE};
 
 pub const LINUX_DISPLAY_CONFIG_KEY: &str = "linuxDisplayConfig";
 
@@ -34,10 +34,10 @@ pub fn read_wayland() -> Option<bool> {
     serde_json::from_value::<DisplayConfig>(root).ok()?.wayland
 }
 
-pub fn write_wayland(app: &AppHandle, value: bool) -> Result<(), String> {
+pub(crate) fn write_wayland(app: &AppHandle, value: bool) -> Result<(), CommandError> {
     let store = app
         .store(SETTINGS_STORE)
-        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+        .map_err(CommandError::settings_open)?;
 
     store.set(
         LINUX_DISPLAY_CONFIG_KEY,
@@ -45,9 +45,7 @@ pub fn write_wayland(app: &AppHandle, value: bool) -> Result<(), String> {
             wayland: Some(value),
         }),
     );
-    store
-        .save()
-        .map_err(|e| format!("Failed to save settings store: {}", e))?;
+    store.save().map_err(CommandError::settings_save)?;
 
     Ok(())
 }
@@ -0,0 +1,781 @@
+//! Reading the app's log files from the frontend, for the in-app log viewer.
+//!
+//! Files are read forward from a byte offset, or backwards from the end, so a page only costs
+//! what it reads however big the file is. Rotated files are compressed, their offsets count
+//! uncompressed bytes and they can only be read forward. Lines that don't start with a timestamp
+//! and level, such as stack traces, belong to the line before them and are filtered along with
+//! it.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use flate2::read::GzDecoder;
+use tauri::{AppHandle, Manager, State, Webview, ipc::Channel};
+
+use crate::{
+    command_error::CommandError, launch_options::SidecarLogLevel, logging, sidecar_log,
+    subscriptions::Subscriptions,
+};
+
+/// Most bytes read for one page, so a filter that matches little doesn't scan a whole file.
+const MAX_PAGE_BYTES: u64 = 4 * 1024 * 1024;
+
+const MAX_PAGE_LINES: u32 = 5000;
+
+/// How often watched files are checked for new lines.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+const DESKTOP_PREFIX: &str = "opencode-desktop_";
+const SIDECAR_PREFIX: &str = "opencode-sidecar_";
+
+#[derive(
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    specta::Type,
+    Debug,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    fn parse(level: &str) -> Option<Self> {
+        match level {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            _ => None,
+        }
+    }
+}
+
+impl From<SidecarLogLevel> for LogLevel {
+    fn from(level: SidecarLogLevel) -> Self {
+        match level {
+            SidecarLogLevel::Debug => Self::Debug,
+            SidecarLogLevel::Info => Self::Info,
+            SidecarLogLevel::Warn => Self::Warn,
+            SidecarLogLevel::Error => Self::Error,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSource {
+    Desktop,
+    Sidecar,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogFileInfo {
+    pub name: String,
+    pub source: LogSource,
+    /// Size on disk, compressed for rotated files.
+    pub size: f64,
+    /// Whether the file was rotated and gzipped.
+    pub compressed: bool,
+    /// RFC 3339.
+    pub modified: Option<String>,
+    /// Whether this run of the app is writing to the file.
+    pub current: bool,
+}
+
+#[derive(Clone, Default, serde::Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogFilter {
+    /// The least severe level to include.
+    pub min_level: Option<LogLevel>,
+    /// Start of the target, e.g. `opencode_lib::server`. For sidecar logs it's matched against
+    /// the service instead.
+    pub target: Option<String>,
+    /// RFC 3339, inclusive.
+    pub since: Option<String>,
+    /// RFC 3339, exclusive.
+    pub until: Option<String>,
+    /// Text the line must contain, ignoring case.
+    pub text: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// Byte offset of the line in its file, uncompressed.
+    pub offset: f64,
+    /// RFC 3339.
+    pub timestamp: Option<String>,
+    pub level: Option<LogLevel>,
+    /// The tracing target, or the service for sidecar logs.
+    pub target: Option<String>,
+    /// The line without its timestamp, level and target.
+    pub message: String,
+    pub line: String,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Where the next page starts. Unset once the end of the file is reached.
+    pub next_offset: Option<f64>,
+    /// Size on disk, compressed for rotated files.
+    pub size: f64,
+}
+
+struct Matcher {
+    min_level: Option<LogLevel>,
+    target: Option<String>,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    text: Option<String>,
+    // Whether the last line with a level matched, which lines without one follow
+    matched: bool,
+}
+
+impl Matcher {
+    fn new(filter: LogFilter) -> Result<Self, CommandError> {
+        let time = |field: &str, value: Option<String>| {
+            value
+                .map(|value| {
+                    DateTime::parse_from_rfc3339(&value)
+                        .map(|time| time.with_timezone(&Utc))
+                        .map_err(|e| {
+                            CommandError::invalid_input(
+                                field,
+                                format!("Invalid time '{}': {}", value, e),
+                            )
+                        })
+                })
+                .transpose()
+        };
+
+        let mut matcher = Self {
+            min_level: filter.min_level,
+            target: filter.target.filter(|target| !target.is_empty()),
+            since: time("since", filter.since)?,
+            until: time("until", filter.until)?,
+            text: filter
+                .text
+                .filter(|text| !text.is_empty())
+                .map(|text| text.to_lowercase()),
+            matched: false,
+        };
+        // Lines without a level before the first one with a level only show unfiltered
+        matcher.matched = matcher.matches_all();
+
+        Ok(matcher)
+    }
+
+    fn matches_all(&self) -> bool {
+        self.min_level.is_none()
+            && self.target.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.text.is_none()
+    }
+
+    fn matches(&mut self, entry: &Parsed) -> bool {
+        let Some(level) = entry.level else {
+            return self.matched;
+        };
+
+        self.matched = self.min_level.is_none_or(|min| level >= min)
+            && self.target.as_deref().is_none_or(|prefix| {
+                entry
+                    .target
+                    .is_some_and(|target| target.starts_with(prefix))
+            })
+            && self
+                .since
+                .is_none_or(|since| entry.timestamp.is_some_and(|time| time >= since))
+            && self
+                .until
+                .is_none_or(|until| entry.timestamp.is_some_and(|time| time < until))
+            && self
+                .text
+                .as_deref()
+                .is_none_or(|text| entry.line.to_lowercase().contains(text));
+        self.matched
+    }
+}
+
+struct Parsed<'a> {
+    timestamp: Option<DateTime<Utc>>,
+    level: Option<LogLevel>,
+    target: Option<&'a str>,
+    message: &'a str,
+    line: &'a str,
+}
+
+impl<'a> Parsed<'a> {
+    /// A line without a timestamp or level.
+    fn raw(line: &'a str) -> Self {
+        Self {
+            timestamp: None,
+            level: None,
+            target: None,
+            message: line,
+            line,
+        }
+    }
+
+    fn into_entry(self, offset: u64) -> LogEntry {
+        LogEntry {
+            offset: offset as f64,
+            timestamp: self
+                .timestamp
+                .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+            level: self.level,
+            target: self.target.map(String::from),
+            message: self.message.to_string(),
+            line: self.line.to_string(),
+        }
+    }
+}
+
+/// Parses a line written by the app's own tracing subscriber, like
+/// `2025-01-01T00:00:00.000000Z  INFO opencode_lib::server: Spawning sidecar`.
+fn parse_desktop(line: &str) -> Parsed<'_> {
+    let parse = || {
+        let (timestamp, rest) = line.split_once(' ')?;
+        let timestamp = DateTime::parse_from_rfc3339(timestamp).ok()?;
+        let (level, mut rest) = rest.trim_start().split_once(' ')?;
+        let level = LogLevel::parse(level)?;
+
+        // Spans come before the target, like `span{id=1}:other{}: target: message`
+        let (target, message) = loop {
+            match rest.split_once(": ") {
+                Some((head, tail)) if head.ends_with('}') => rest = tail,
+                Some((head, tail)) if !head.contains(' ') => break (Some(head), tail),
+                _ => break (None, rest),
+            }
+        };
+
+        Some(Parsed {
+            timestamp: Some(timestamp.with_timezone(&Utc)),
+            level: Some(level),
+            target,
+            message,
+            line,
+        })
+    };
+
+    parse().unwrap_or(Parsed::raw(line))
+}
+
+fn parse_sidecar(line: &str) -> Parsed<'_> {
+    match sidecar_log::parse(line) {
+        Some(parsed) => Parsed {
+            timestamp: chrono::NaiveDateTime::parse_from_str(parsed.timestamp, "%Y-%m-%dT%H:%M:%S")
+                .ok()
+                .map(|time| time.and_utc()),
+            level: Some(parsed.level.into()),
+            target: parsed.service,
+            message: parsed.message,
+            line,
+        },
+        None => Parsed::raw(line),
+    }
+}
+
+fn parse_line(source: LogSource, line: &str) -> Parsed<'_> {
+    match source {
+        LogSource::Desktop => parse_desktop(line),
+        LogSource::Sidecar => parse_sidecar(line),
+    }
+}
+
+fn source_of(name: &str) -> Option<LogSource> {
+    if !logging::is_log_file(name) {
+        return None;
+    }
+    if name.starts_with(DESKTOP_PREFIX) {
+        Some(LogSource::Desktop)
+    } else if name.starts_with(SIDECAR_PREFIX) {
+        Some(LogSource::Sidecar)
+    } else {
+        None
+    }
+}
+
+fn log_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    app.path()
+        .app_log_dir()
+        .map_err(|e| CommandError::internal(format!("Failed to resolve log directory: {}", e)))
+}
+
+/// The path of a log file by name, refusing anything outside the log directory.
+fn resolve(app: &AppHandle, name: &str) -> Result<(PathBuf, LogSource), CommandError> {
+    let source = source_of(name)
+        .filter(|_| Path::new(name).file_name().is_some_and(|file| file == name))
+        .ok_or_else(|| {
+            CommandError::invalid_input("name", format!("'{}' isn't a log file of the app", name))
+        })?;
+
+    Ok((log_dir(app)?.join(name), source))
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Reads matching entries from `offset` on, stopping after `limit` entries. A last line that
+/// hasn't been finished yet is left for the next read. Also returns where reading stopped and
+/// whether that's the end of the file.
+fn scan(
+    path: &Path,
+    source: LogSource,
+    offset: u64,
+    limit: usize,
+    matcher: &mut Matcher,
+) -> std::io::Result<(Vec<LogEntry>, u64, bool)> {
+    let mut file = File::open(path)?;
+    let mut reader: Box<dyn BufRead> = if is_compressed(path) {
+        // Compressed files can't seek, skip to the offset instead
+        let mut decoder = BufReader::new(GzDecoder::new(file));
+        std::io::copy(&mut (&mut decoder).take(offset), &mut std::io::sink())?;
+        Box::new(decoder)
+    } else {
+        file.seek(SeekFrom::Start(offset))?;
+        Box::new(BufReader::new(file))
+    };
+
+    let mut entries = vec![];
+    let mut position = offset;
+    let mut buf = vec![];
+    let mut at_end = false;
+
+    while entries.len() < limit && position - offset < MAX_PAGE_BYTES {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf)?;
+        if read == 0 || buf.last() != Some(&b'\n') {
+            at_end = true;
+            break;
+        }
+
+        let line = String::from_utf8_lossy(&buf);
+        let parsed = parse_line(source, line.trim_end());
+        if matcher.matches(&parsed) {
+            entries.push(parsed.into_entry(position));
+        }
+
+        position += read as u64;
+    }
+
+    Ok((entries, position, at_end))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_log_files(app: AppHandle) -> Result<Vec<LogFileInfo>, CommandError> {
+    let dir = log_dir(&app)?;
+    let entries = std::fs::read_dir(&dir)
+        .map_err(|e| CommandError::io(&dir, format!("Failed to read log directory: {}", e)))?;
+
+    let current = [logging::path(), sidecar_log::path()];
+
+    let mut files: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let source = source_of(&name)?;
+            let meta = entry.metadata().ok().filter(|meta| meta.is_file())?;
+
+            Some(LogFileInfo {
+                source,
+                size: meta.len() as f64,
+                compressed: name.ends_with(".gz"),
+                modified: meta.modified().ok().map(|time| {
+                    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+                }),
+                current: current.iter().flatten().any(|path| *path == entry.path()),
+                name,
+            })
+        })
+        .collect();
+
+    // Newest first, timestamps in the names sort chronologically
+    files.sort_by(|a, b| b.name.cmp(&a.name));
+
+    Ok(files)
+}
+
+/// Reads up to `limit` entries matching `filter` from the log file `name`, starting at the
+/// byte `offset`.
+#[tauri::command]
+#[specta::specta]
+pub async fn read_log_file(
+    app: AppHandle,
+    name: String,
+    filter: LogFilter,
+    offset: Option<f64>,
+    limit: u32,
+) -> Result<LogPage, CommandError> {
+    let (path, source) = resolve(&app, &name)?;
+    let mut matcher = Matcher::new(filter)?;
+
+    let offset = offset.unwrap_or_default().max(0.0) as u64;
+    let limit = limit.clamp(1, MAX_PAGE_LINES) as usize;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let size = std::fs::metadata(&path)
+            .map_err(|e| CommandError::io(&path, format!("Failed to read {}: {}", name, e)))?
+            .len();
+        let (entries, next, at_end) = scan(&path, source, offset, limit, &mut matcher)
+            .map_err(|e| CommandError::io(&path, format!("Failed to read {}: {}", name, e)))?;
+
+        Ok(LogPage {
+            entries,
+            next_offset: (!at_end).then_some(next as f64),
+            size: size as f64,
+        })
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+}
+
+/// Reads up to `limit` entries matching `filter` from the end of the log file `name`, or from
+/// before the byte `before`. The page's `nextOffset` is where the page before it ends, unset
+/// once the start of the file is reached.
+#[tauri::command]
+#[specta::specta]
+pub async fn tail_log_file(
+    app: AppHandle,
+    name: String,
+    filter: LogFilter,
+    before: Option<f64>,
+    limit: u32,
+) -> Result<LogPage, CommandError> {
+    let (path, source) = resolve(&app, &name)?;
+    if is_compressed(&path) {
+        return Err(CommandError::invalid_input(
+            "name",
+            format!(
+                "'{}' is compressed and can only be read from the start",
+                name
+            ),
+        ));
+    }
+    let mut matcher = Matcher::new(filter)?;
+
+    let before = before.map(|before| before.max(0.0) as u64);
+    let limit = limit.clamp(1, MAX_PAGE_LINES) as usize;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        tail_page(&path, source, &mut matcher, before, limit)
+            .map_err(|e| CommandError::io(&path, format!("Failed to read {}: {}", name, e)))
+    })
+    .await
+    .map_err(|e| CommandError::internal(e.to_string()))?
+}
+
+/// Reads the page of up to `limit` entries ending at `before`, see [`tail_log_file`].
+fn tail_page(
+    path: &Path,
+    source: LogSource,
+    matcher: &mut Matcher,
+    before: Option<u64>,
+    limit: usize,
+) -> std::io::Result<LogPage> {
+    // Without a filter every line is an entry, so no more lines are needed than that
+    let max_lines = if matcher.matches_all() {
+        limit
+    } else {
+        usize::MAX
+    };
+
+    let size = std::fs::metadata(path)?.len();
+    let lines = logging::tail(path, before, MAX_PAGE_BYTES, max_lines)?;
+
+    let mut entries: Vec<_> = lines
+        .iter()
+        .filter_map(|line| {
+            let parsed = parse_line(source, &line.text);
+            matcher
+                .matches(&parsed)
+                .then(|| parsed.into_entry(line.offset))
+        })
+        .collect();
+    let skip = entries.len().saturating_sub(limit);
+    entries.drain(..skip);
+
+    // Entries left out above are read again with the page before
+    let start = entries
+        .first()
+        .filter(|_| skip > 0)
+        .map(|entry| entry.offset as u64)
+        .or(lines.first().map(|line| line.offset))
+        .unwrap_or_default();
+
+    Ok(LogPage {
+        entries,
+        next_offset: (start > 0).then_some(start as f64),
+        size: size as f64,
+    })
+}
+
+/// The part of the log `source` that's currently written to.
+fn current_path(source: LogSource) -> Option<PathBuf> {
+    match source {
+        LogSource::Desktop => logging::path(),
+        LogSource::Sidecar => sidecar_log::path(),
+    }
+}
+
+/// Reads what was appended to `path` since `offset`. If `follow` is set and the log has moved on
+/// to a new part, reads the rest of `path`, or of its compressed copy once it's gone, and also
+/// returns the new part.
+fn read_appended(
+    path: &Path,
+    source: LogSource,
+    offset: u64,
+    follow: bool,
+    matcher: &mut Matcher,
+) -> std::io::Result<(Vec<LogEntry>, u64, Option<PathBuf>)> {
+    let moved_to = || current_path(source).filter(|current| follow && current != path);
+    // Checked first, a part that rotated before it's read won't get more lines
+    let rotated = moved_to();
+
+    let len = match std::fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let Some(next) = rotated.or_else(moved_to) else {
+                return Err(e);
+            };
+            let mut compressed = path.as_os_str().to_owned();
+            compressed.push(".gz");
+            let entries = match scan(Path::new(&compressed), source, offset, usize::MAX, matcher) {
+                Ok((entries, _, _)) => entries,
+                // Already cleaned up as well
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+                Err(e) => return Err(e),
+            };
+            return Ok((entries, 0, Some(next)));
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Truncated, start over
+    let from = if len < offset { 0 } else { offset };
+    let (entries, next) = if len == from {
+        (vec![], from)
+    } else {
+        let (entries, next, _) = scan(path, source, from, usize::MAX, matcher)?;
+        (entries, next)
+    };
+
+    match rotated {
+        Some(next_path) => Ok((entries, 0, Some(next_path))),
+        None => Ok((entries, next, None)),
+    }
+}
+
+/// Sends entries matching `filter` as they're appended to the log file `name`, until the
+/// file goes away or it's stopped with `unsubscribe` and the returned id. When the file is the
+/// one being written to, the watcher moves on to its next part as it rotates.
+#[tauri::command]
+#[specta::specta]
+pub async fn watch_log_file(
+    app: AppHandle,
+    webview: Webview,
+    subscriptions: State<'_, Subscriptions>,
+    name: String,
+    filter: LogFilter,
+    events: Channel<LogEntry>,
+) -> Result<u32, CommandError> {
+    let (mut path, source) = resolve(&app, &name)?;
+    if is_compressed(&path) {
+        return Err(CommandError::invalid_input(
+            "name",
+            format!("'{}' was rotated and won't change anymore", name),
+        ));
+    }
+    let mut matcher = Matcher::new(filter)?;
+    let follow = current_path(source).as_ref() == Some(&path);
+
+    let mut offset = std::fs::metadata(&path)
+        .map_err(|e| CommandError::io(&path, format!("Failed to read {}: {}", name, e)))?
+        .len();
+
+    Ok(subscriptions.spawn(&webview, async move {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+
+            let read_path = path.clone();
+            let read = tauri::async_runtime::spawn_blocking(move || {
+                read_appended(&read_path, source, offset, follow, &mut matcher)
+                    .map(|read| (read, matcher))
+            })
+            .await;
+
+            let (entries, next, moved_to) = match read {
+                Ok(Ok((read, returned))) => {
+                    matcher = returned;
+                    read
+                }
+                // Gone, e.g. cleaned up
+                Ok(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => return,
+                Ok(Err(e)) => {
+                    tracing::warn!("Stopped watching {name}: {e}");
+                    return;
+                }
+                Err(e) => {
+                    tracing::warn!("Stopped watching {name}: {e}");
+                    return;
+                }
+            };
+            offset = next;
+            if let Some(moved_to) = moved_to {
+                tracing::debug!(path = %moved_to.display(), "Log rotated, watching the next part");
+                path = moved_to;
+            }
+
+            for entry in entries {
+                if events.send(entry).is_err() {
+                    return;
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINES: usize = 100;
+
+    /// A desktop log where every third line is a warning and every fifth is followed by a
+    /// line without a level.
+    fn write_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "opencode-log-browser-{}-{}.log",
+            name,
+            std::process::id()
+        ));
+
+        let mut contents = String::new();
+        for i in 0..LINES {
+            let level = if i % 3 == 0 { "WARN" } else { "INFO" };
+            contents.push_str(&format!(
+                "2025-01-01T00:00:{:02}.000000Z  {level} opencode_lib::server: message {i}\n",
+                i % 60
+            ));
+            if i % 5 == 0 {
+                contents.push_str(&format!("    detail {i}\n"));
+            }
+        }
+        std::fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    /// Pages backwards from the end until the start, returning the lines oldest first.
+    fn page_all(path: &Path, filter: LogFilter, limit: usize) -> Vec<String> {
+        let mut pages = vec![];
+        let mut before = None;
+        loop {
+            let mut matcher = Matcher::new(filter.clone()).unwrap();
+            let page = tail_page(path, LogSource::Desktop, &mut matcher, before, limit).unwrap();
+            assert!(page.entries.len() <= limit);
+            pages.push(page.entries);
+
+            match page.next_offset {
+                Some(next) => before = Some(next as u64),
+                None => break,
+            }
+        }
+
+        pages
+            .into_iter()
+            .rev()
+            .flatten()
+            .map(|entry| entry.line)
+            .collect()
+    }
+
+    #[test]
+    fn tail_pages_cover_file_once() {
+        let path = write_log("unfiltered");
+        let all: Vec<_> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+
+        for limit in [1, 7, 50, 500] {
+            assert_eq!(
+                page_all(&path, LogFilter::default(), limit),
+                all,
+                "limit {limit}"
+            );
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn filtered_tail_pages_cover_matches_once() {
+        let path = write_log("filtered");
+        let filter = LogFilter {
+            min_level: Some(LogLevel::Warn),
+            ..Default::default()
+        };
+
+        let mut matcher = Matcher::new(filter.clone()).unwrap();
+        let expected: Vec<_> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .filter(|line| matcher.matches(&parse_desktop(line)))
+            .map(String::from)
+            .collect();
+        // Warnings and the lines without a level that follow them
+        assert_eq!(expected.len(), 34 + 7);
+
+        for limit in [1, 4, 100] {
+            assert_eq!(
+                page_all(&path, filter.clone(), limit),
+                expected,
+                "limit {limit}"
+            );
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tail_page_ends_at_last_entry() {
+        let path = write_log("last");
+
+        let mut matcher = Matcher::new(LogFilter::default()).unwrap();
+        let page = tail_page(&path, LogSource::Desktop, &mut matcher, None, 2).unwrap();
+        let lines: Vec<_> = page
+            .entries
+            .iter()
+            .map(|entry| entry.message.as_str())
+            .collect();
+        assert_eq!(lines, ["message 98", "message 99"]);
+        assert_eq!(page.next_offset, Some(page.entries[0].offset));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
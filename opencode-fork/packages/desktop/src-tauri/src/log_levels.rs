@@ -0,0 +1,190 @@
+//! Changing how much the app and the sidecar log while the app is running.
+//!
+//! The desktop filter is swapped in place. The sidecar only reads its level at startup, so
+//! changing it restarts the sidecar. Debug logging turns both up for a while and then puts back
+//! what was there before.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::AppHandle;
+
+use crate::{
+    command_error::CommandError,
+    launch_options::{self, SidecarLogLevel},
+    logging, server,
+};
+
+/// Longest debug logging can be turned on for.
+const MAX_DEBUG_MINUTES: u32 = 24 * 60;
+
+static DEBUG: Mutex<Option<DebugLogging>> = Mutex::new(None);
+
+struct DebugLogging {
+    until: Instant,
+    /// The desktop filter to put back when debug logging ends.
+    previous_filter: String,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevels {
+    /// The desktop app's filter, in `RUST_LOG` syntax.
+    pub desktop_filter: String,
+    /// The level the sidecar is started with.
+    pub sidecar_level: SidecarLogLevel,
+    /// Seconds left until debug logging ends, if it's on.
+    pub debug_remaining_secs: Option<u32>,
+}
+
+/// The level to start the sidecar with, given the one in its launch options.
+pub fn sidecar_level(configured: SidecarLogLevel) -> SidecarLogLevel {
+    match *DEBUG.lock().unwrap() {
+        Some(_) => SidecarLogLevel::Debug,
+        None => configured,
+    }
+}
+
+async fn restart_sidecar(app: &AppHandle) {
+    if let Err(e) = server::restart_sidecar(app.clone()).await {
+        tracing::warn!("Sidecar not restarted for its new log level: {e}");
+    }
+}
+
+fn set_filter(directives: &str) -> Result<(), CommandError> {
+    logging::set_filter(directives).map_err(|e| CommandError::invalid_input("filter", e))
+}
+
+/// Puts back the desktop filter and sidecar level from before debug logging, if it's on.
+async fn end_debug_logging(app: &AppHandle) -> Result<(), CommandError> {
+    let Some(debug) = DEBUG.lock().unwrap().take() else {
+        return Ok(());
+    };
+
+    tracing::info!("Debug logging ended");
+    set_filter(&debug.previous_filter)?;
+    restart_sidecar(app).await;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_log_levels(app: AppHandle) -> LogLevels {
+    LogLevels {
+        desktop_filter: logging::filter().unwrap_or_default(),
+        sidecar_level: sidecar_level(launch_options::read(&app).log_level),
+        debug_remaining_secs: DEBUG.lock().unwrap().as_ref().map(|debug| {
+            u32::try_from(
+                debug
+                    .until
+                    .saturating_duration_since(Instant::now())
+                    .as_secs(),
+            )
+            .unwrap_or(u32::MAX)
+        }),
+    }
+}
+
+/// Replaces the desktop filter, or restores the default one if `filter` is unset. While debug
+/// logging is on, the filter is kept for when it ends.
+#[tauri::command]
+#[specta::specta]
+pub fn set_desktop_log_filter(filter: Option<String>) -> Result<(), CommandError> {
+    let filter = filter
+        .filter(|filter| !filter.trim().is_empty())
+        .unwrap_or_else(|| logging::DEFAULT_FILTER.to_string());
+
+    set_filter(&filter)?;
+    if let Some(debug) = DEBUG.lock().unwrap().as_mut() {
+        debug.previous_filter = filter.clone();
+    }
+
+    tracing::info!(%filter, "Desktop log filter changed");
+
+    Ok(())
+}
+
+/// Saves the sidecar's log level, restarting it right away if `restart` is set.
+#[tauri::command]
+#[specta::specta]
+pub async fn set_sidecar_log_level(
+    app: AppHandle,
+    level: SidecarLogLevel,
+    restart: bool,
+) -> Result<(), CommandError> {
+    let options = launch_options::SidecarLaunchOptions {
+        log_level: level,
+        ..launch_options::read(&app)
+    };
+    let changed = launch_options::set_sidecar_launch_options(app.clone(), options)?;
+    let debugging = DEBUG.lock().unwrap().is_some();
+
+    tracing::info!(?level, "Sidecar log level changed");
+
+    // Debug logging already runs the sidecar at the most verbose level
+    if restart && changed && !debugging {
+        restart_sidecar(&app).await;
+    }
+
+    Ok(())
+}
+
+/// Turns on debug logging for the app and the sidecar for the given number of minutes, after
+/// which the previous levels are restored. Calling it again while it's on extends it.
+#[tauri::command]
+#[specta::specta]
+pub async fn start_debug_logging(app: AppHandle, minutes: u32) -> Result<(), CommandError> {
+    if minutes == 0 || minutes > MAX_DEBUG_MINUTES {
+        return Err(CommandError::invalid_input(
+            "minutes",
+            format!("Debug logging can be turned on for 1 to {MAX_DEBUG_MINUTES} minutes"),
+        ));
+    }
+
+    let duration = Duration::from_secs(u64::from(minutes) * 60);
+    let until = Instant::now() + duration;
+
+    let extended = {
+        let mut debug = DEBUG.lock().unwrap();
+        let extended = debug.is_some();
+        let previous_filter = match debug.take() {
+            Some(debug) => debug.previous_filter,
+            None => logging::filter().unwrap_or_else(|| logging::DEFAULT_FILTER.to_string()),
+        };
+        *debug = Some(DebugLogging {
+            until,
+            previous_filter,
+        });
+        extended
+    };
+
+    set_filter(logging::DEBUG_FILTER)?;
+    tracing::info!(minutes, "Debug logging started");
+
+    if !extended {
+        restart_sidecar(&app).await;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(duration).await;
+
+        // Skip if it was stopped or extended in the meantime
+        let current = DEBUG.lock().unwrap().as_ref().map(|debug| debug.until);
+        if current == Some(until)
+            && let Err(e) = end_debug_logging(&app).await
+        {
+            tracing::warn!("Failed to end debug logging: {e}");
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn stop_debug_logging(app: AppHandle) -> Result<(), CommandError> {
+    end_debug_logging(&app).await
+}
@@ -1,83 +1,725 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::{Duration, SystemTime};
+
+use flate2::{Compression, write::GzEncoder};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
 use tracing_appender::non_blocking::WorkerGuard;
-use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+use tracing_subscriber::{
+    EnvFilter, Layer, Registry, filter,
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    reload,
+    util::SubscriberInitExt,
+};
+
+use crate::{
+    command_error::CommandError,
+    constants::{LOG_RETENTION_KEY, SETTINGS_STORE},
+    redact::Redacting,
+};
+
+/// Filter used when `RUST_LOG` isn't set.
+pub const DEFAULT_FILTER: &str = if cfg!(debug_assertions) {
+    DEBUG_FILTER
+} else {
+    "opencode_lib=info,opencode_desktop=info,sidecar=info"
+};
+
+pub const DEBUG_FILTER: &str = "opencode_lib=debug,opencode_desktop=debug,sidecar=debug";
+
+/// Names of the log files this app writes start with one of these.
+pub const LOG_PREFIXES: &[&str] = &["opencode-desktop_", "opencode-sidecar_"];
+
+static LOG_PATH: std::sync::OnceLock<Arc<Mutex<PathBuf>>> = std::sync::OnceLock::new();
+
+static FILTER: std::sync::OnceLock<reload::Handle<EnvFilter, Registry>> =
+    std::sync::OnceLock::new();
+
+/// How much log history is kept. Rotation takes effect the next time the app starts.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LogRetention {
+    /// Files older than this are deleted.
+    pub max_age_days: u32,
+    /// The oldest files are deleted while all of them together are larger than this.
+    pub max_total_mb: u32,
+    /// Size at which a log file is closed, compressed and a new one started.
+    pub max_file_mb: u32,
+}
+
+impl Default for LogRetention {
+    fn default() -> Self {
+        Self {
+            max_age_days: 7,
+            max_total_mb: 500,
+            max_file_mb: 50,
+        }
+    }
+}
+
+impl LogRetention {
+    fn max_file_bytes(&self) -> u64 {
+        u64::from(self.max_file_mb) * 1024 * 1024
+    }
+}
+
+/// A log file that moves on to a new part once it reaches its size limit. Finished parts are
+/// compressed in the background.
+pub struct RotatingFile {
+    dir: PathBuf,
+    stem: String,
+    part: u32,
+    size: u64,
+    file: File,
+    retention: LogRetention,
+    path: Arc<Mutex<PathBuf>>,
+}
+
+impl RotatingFile {
+    /// Creates `<stem>.log` in `dir`, with later parts named `<stem>.<part>.log`.
+    pub fn create(dir: &Path, stem: String, retention: LogRetention) -> std::io::Result<Self> {
+        let path = dir.join(format!("{stem}.log"));
+        Ok(Self {
+            file: File::create(&path)?,
+            dir: dir.to_path_buf(),
+            stem,
+            part: 0,
+            size: 0,
+            retention,
+            path: Arc::new(Mutex::new(path)),
+        })
+    }
 
-const MAX_LOG_AGE_DAYS: u64 = 7;
-const TAIL_LINES: usize = 1000;
+    /// The part currently written to, kept up to date as the file rotates.
+    pub fn path(&self) -> Arc<Mutex<PathBuf>> {
+        self.path.clone()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let part = self.part + 1;
+        let path = self.dir.join(format!("{}.{part}.log", self.stem));
+        self.file = File::create(&path)?;
+        self.part = part;
+        self.size = 0;
+
+        let finished = std::mem::replace(&mut *self.path.lock().unwrap(), path);
+        let dir = self.dir.clone();
+        let retention = self.retention;
+        std::thread::spawn(move || {
+            if let Err(e) = compress(&finished) {
+                tracing::warn!(path = %finished.display(), "Failed to compress log file: {e}");
+            }
+            cleanup(&dir, &retention);
+        });
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.retention.max_file_bytes() {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
 
-static LOG_PATH: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Replaces `path` with a gzipped copy named `<path>.gz`.
+fn compress(path: &Path) -> std::io::Result<()> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    let gz_path = PathBuf::from(name);
+
+    let mut source = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(&gz_path)?, Compression::default());
+    std::io::copy(&mut source, &mut encoder)?;
+    encoder.finish()?;
+
+    std::fs::remove_file(path)
+}
+
+pub fn read_retention(app: &AppHandle) -> LogRetention {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(LOG_RETENTION_KEY))
+        .and_then(|v| {
+            serde_json::from_value(v)
+                .inspect_err(|e| tracing::warn!("Invalid log retention setting: {e}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
 
-pub fn init(log_dir: &Path) -> WorkerGuard {
+pub fn init(log_dir: &Path, retention: LogRetention) -> WorkerGuard {
     std::fs::create_dir_all(log_dir).expect("failed to create log directory");
 
-    cleanup(log_dir);
+    cleanup(log_dir, &retention);
 
     let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
-    let filename = format!("opencode-desktop_{timestamp}.log");
-    let log_path = log_dir.join(&filename);
+    let file = RotatingFile::create(log_dir, format!("opencode-desktop_{timestamp}"), retention)
+        .expect("failed to create log file");
 
     LOG_PATH
-        .set(log_path.clone())
+        .set(file.path())
         .expect("logging already initialized");
 
-    let file = File::create(&log_path).expect("failed to create log file");
     let (non_blocking, guard) = tracing_appender::non_blocking(file);
 
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| {
-        if cfg!(debug_assertions) {
-            EnvFilter::new("opencode_lib=debug,opencode_desktop=debug,sidecar=debug")
-        } else {
-            EnvFilter::new("opencode_lib=info,opencode_desktop=info,sidecar=info")
-        }
-    });
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let (filter, handle) = reload::Layer::new(filter);
+    let _ = FILTER.set(handle);
 
     tracing_subscriber::registry()
         .with(filter)
-        .with(fmt::layer().with_writer(std::io::stderr))
-        .with(
-            fmt::layer()
-                .with_writer(non_blocking)
-                .with_ansi(false),
-        )
+        .with(fmt::layer().with_writer(Redacting(std::io::stderr)))
+        .with(file_layer(non_blocking))
         .init();
 
     guard
 }
 
-pub fn tail() -> String {
-    let Some(path) = LOG_PATH.get() else {
-        return String::new();
+/// The layer writing the log file, with secrets masked.
+pub fn file_layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    fmt::layer()
+        .with_writer(Redacting(writer))
+        .with_ansi(false)
+        // Sidecar output has a log file of its own
+        .with_filter(filter::filter_fn(|meta| meta.target() != "sidecar"))
+}
+
+/// The filter currently applied, in `RUST_LOG` syntax.
+pub fn filter() -> Option<String> {
+    FILTER.get()?.with_current(|filter| filter.to_string()).ok()
+}
+
+/// Replaces the filter without restarting the app.
+pub fn set_filter(directives: &str) -> Result<(), String> {
+    let filter = EnvFilter::try_new(directives)
+        .map_err(|e| format!("Invalid log filter '{}': {}", directives, e))?;
+
+    FILTER
+        .get()
+        .ok_or("Logging isn't initialized")?
+        .reload(filter)
+        .map_err(|e| format!("Failed to apply log filter: {}", e))
+}
+
+/// The log file this run is currently writing to.
+pub fn path() -> Option<PathBuf> {
+    Some(LOG_PATH.get()?.lock().unwrap().clone())
+}
+
+/// Like [`path`], but returns nothing rather than waiting for it, for the panic hook.
+pub fn try_path() -> Option<PathBuf> {
+    let path = match LOG_PATH.get()?.try_lock() {
+        Ok(path) => path,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return None,
+    };
+
+    Some(path.clone())
+}
+
+/// How much is read at a time by [`tail`].
+const TAIL_CHUNK_BYTES: u64 = 8 * 1024;
+
+pub struct TailLine {
+    /// Byte offset of the line in its file.
+    pub offset: u64,
+    pub text: String,
+}
+
+/// Reads the last complete lines of the file at `path` before `end`, or before its end if
+/// unset, seeking backwards so only what's returned is read. Stops after `max_lines` lines or
+/// `max_bytes` bytes, whichever comes first. The file may be appended to meanwhile: what's
+/// written after the call starts, and a last line that isn't finished, are left out.
+pub fn tail(
+    path: &Path,
+    end: Option<u64>,
+    max_bytes: u64,
+    max_lines: usize,
+) -> std::io::Result<Vec<TailLine>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let end = end.map_or(len, |end| end.min(len));
+    let floor = end.saturating_sub(max_bytes);
+
+    // Chunks from the end backwards, until one more line break than lines wanted is found
+    let mut chunks = vec![];
+    let mut start = end;
+    let mut breaks = 0;
+    while start > floor && breaks <= max_lines {
+        let from = start.saturating_sub(TAIL_CHUNK_BYTES).max(floor);
+        let mut chunk = vec![0; (start - from) as usize];
+        file.seek(SeekFrom::Start(from))?;
+        file.read_exact(&mut chunk)?;
+
+        breaks += chunk.iter().filter(|&&b| b == b'\n').count();
+        chunks.push(chunk);
+        start = from;
+    }
+    let buf: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+
+    // Whether the first line starts at `start` or was cut off
+    let whole_first = start == 0 || {
+        let mut before = [0];
+        file.seek(SeekFrom::Start(start - 1))?;
+        file.read_exact(&mut before)?;
+        before[0] == b'\n'
     };
 
-    let Ok(file) = File::open(path) else {
-        return String::new();
+    let Some(last_break) = buf.iter().rposition(|&b| b == b'\n') else {
+        return Ok(vec![]);
+    };
+    let first = if whole_first {
+        0
+    } else {
+        match buf.iter().position(|&b| b == b'\n') {
+            Some(cut) if cut < last_break => cut + 1,
+            _ => return Ok(vec![]),
+        }
     };
 
-    let lines: Vec<String> = BufReader::new(file)
-        .lines()
-        .map_while(Result::ok)
+    let mut offset = start + first as u64;
+    let mut lines: Vec<_> = buf[first..last_break]
+        .split(|&b| b == b'\n')
+        .map(|line| {
+            let tail_line = TailLine {
+                offset,
+                text: String::from_utf8_lossy(line)
+                    .trim_end_matches('\r')
+                    .to_string(),
+            };
+            offset += line.len() as u64 + 1;
+            tail_line
+        })
         .collect();
+    let skip = lines.len().saturating_sub(max_lines);
+    lines.drain(..skip);
 
-    let start = lines.len().saturating_sub(TAIL_LINES);
-    lines[start..].join("\n")
+    Ok(lines)
 }
 
-fn cleanup(log_dir: &Path) {
-    let cutoff = std::time::SystemTime::now()
-        - std::time::Duration::from_secs(MAX_LOG_AGE_DAYS * 24 * 60 * 60);
+/// Whether `name` is a log file this app wrote, compressed or not.
+pub fn is_log_file(name: &str) -> bool {
+    LOG_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+        && (name.ends_with(".log") || name.ends_with(".log.gz"))
+}
 
+/// Deletes log files past the retention limits. Only files this app wrote are touched, and
+/// never the ones currently written to.
+fn cleanup(log_dir: &Path, retention: &LogRetention) {
     let Ok(entries) = std::fs::read_dir(log_dir) else {
         return;
     };
 
-    for entry in entries.flatten() {
-        if let Ok(meta) = entry.metadata()
-            && let Ok(modified) = meta.modified()
-            && modified < cutoff
-        {
-            let _ = std::fs::remove_file(entry.path());
+    let current = [path(), crate::sidecar_log::path()];
+    let cutoff =
+        SystemTime::now() - Duration::from_secs(u64::from(retention.max_age_days) * 24 * 60 * 60);
+
+    let mut files: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(is_log_file))
+        .filter(|entry| !current.iter().flatten().any(|path| *path == entry.path()))
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok().filter(|meta| meta.is_file())?;
+            Some((entry.path(), meta.modified().ok()?, meta.len()))
+        })
+        .collect();
+
+    // Newest first, so the oldest are the ones over the size limit
+    files.sort_by(|a, b| b.1.cmp(&a.1));
+
+    let max_total = u64::from(retention.max_total_mb) * 1024 * 1024;
+    let mut total = 0;
+    for (path, modified, size) in files {
+        total += size;
+        if modified < cutoff || total > max_total {
+            let _ = std::fs::remove_file(path);
         }
     }
 }
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_log_retention(app: AppHandle) -> LogRetention {
+    read_retention(&app)
+}
+
+/// Saves the retention limits and applies the age and total size limits right away.
+#[tauri::command]
+#[specta::specta]
+pub fn set_log_retention(app: AppHandle, retention: LogRetention) -> Result<(), CommandError> {
+    if retention.max_age_days == 0 || retention.max_total_mb == 0 || retention.max_file_mb == 0 {
+        return Err(CommandError::invalid_input(
+            "retention",
+            "Log retention limits must be greater than zero",
+        ));
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(LOG_RETENTION_KEY, serde_json::json!(retention));
+    store.save().map_err(CommandError::settings_save)?;
+
+    if let Ok(dir) = app.path().app_log_dir() {
+        cleanup(&dir, &retention);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn log_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("opencode-tail-{}-{}.log", name, std::process::id()))
+    }
+
+    fn write_log(name: &str, contents: &[u8]) -> PathBuf {
+        let path = log_path(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn texts(lines: &[TailLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_last_lines_with_offsets() {
+        let path = write_log("last", b"one\ntwo\r\nthree\nfour\n");
+
+        let lines = tail(&path, None, u64::MAX, 2).unwrap();
+        assert_eq!(texts(&lines), ["three", "four"]);
+        assert_eq!(lines[0].offset, 9);
+        assert_eq!(lines[1].offset, 15);
+
+        let lines = tail(&path, None, u64::MAX, 10).unwrap();
+        assert_eq!(texts(&lines), ["one", "two", "three", "four"]);
+        assert_eq!(lines[1].offset, 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty_file_has_no_lines() {
+        let path = write_log("empty", b"");
+
+        assert!(tail(&path, None, u64::MAX, 10).unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn leaves_out_unfinished_last_line() {
+        let path = write_log("unfinished", b"one\ntwo\nthr");
+
+        let lines = tail(&path, None, u64::MAX, 10).unwrap();
+        assert_eq!(texts(&lines), ["one", "two"]);
+
+        let path = write_log("unfinished-only", b"partial");
+        assert!(tail(&path, None, u64::MAX, 10).unwrap().is_empty());
+
+        std::fs::remove_file(log_path("unfinished")).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reads_lines_across_chunks() {
+        // A line straddling the boundary of the last chunk and the one before it
+        let long = "x".repeat(TAIL_CHUNK_BYTES as usize);
+        let contents = format!("first\n{long}\nlast\n");
+        let path = write_log("chunks", contents.as_bytes());
+
+        let lines = tail(&path, None, u64::MAX, 2).unwrap();
+        assert_eq!(texts(&lines), [long.as_str(), "last"]);
+        assert_eq!(lines[0].offset, 6);
+
+        let lines = tail(&path, None, u64::MAX, 10).unwrap();
+        assert_eq!(texts(&lines), ["first", long.as_str(), "last"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn byte_limit_drops_line_cut_in_half() {
+        let path = write_log("bytes", b"one\ntwo\nthree\n");
+
+        // Starts in the middle of "two"
+        let lines = tail(&path, None, 8, 10).unwrap();
+        assert_eq!(texts(&lines), ["three"]);
+        assert_eq!(lines[0].offset, 8);
+
+        // Starts right at "two"
+        let lines = tail(&path, None, 10, 10).unwrap();
+        assert_eq!(texts(&lines), ["two", "three"]);
+
+        // Nothing complete fits
+        assert!(tail(&path, None, 3, 10).unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn end_at_line_start_reads_lines_before_it() {
+        let path = write_log("end", b"one\ntwo\nthree\nfour\n");
+
+        // 8 is where "three" starts
+        let lines = tail(&path, Some(8), u64::MAX, 10).unwrap();
+        assert_eq!(texts(&lines), ["one", "two"]);
+
+        // Continuing from the first line returned pages back without gaps or repeats
+        let lines = tail(&path, Some(lines[1].offset), u64::MAX, 10).unwrap();
+        assert_eq!(texts(&lines), ["one"]);
+
+        // In the middle of a line, that line isn't finished yet
+        let lines = tail(&path, Some(10), u64::MAX, 10).unwrap();
+        assert_eq!(texts(&lines), ["one", "two"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn only_complete_lines_while_appended_to() {
+        const LINES: usize = 20_000;
+        // "line 000000" and its line break
+        const LINE_LEN: u64 = 12;
+
+        let path = write_log("appending", b"");
+        let done = Arc::new(AtomicBool::new(false));
+
+        let writer = {
+            let path = path.clone();
+            let done = done.clone();
+            std::thread::spawn(move || {
+                let mut file = std::fs::OpenOptions::new()
+                    .append(true)
+                    .open(&path)
+                    .unwrap();
+                for i in 0..LINES {
+                    // Two writes, so readers can see half a line
+                    let line = format!("line {i:06}\n");
+                    file.write_all(&line.as_bytes()[..7]).unwrap();
+                    file.write_all(&line.as_bytes()[7..]).unwrap();
+                }
+                done.store(true, Ordering::SeqCst);
+            })
+        };
+
+        let mut reads = 0;
+        while !done.load(Ordering::SeqCst) || reads == 0 {
+            let lines = tail(&path, None, 64 * 1024, 50).unwrap();
+            for (i, line) in lines.iter().enumerate() {
+                let n: u64 = line.text.strip_prefix("line ").unwrap().parse().unwrap();
+                assert_eq!(line.offset, n * LINE_LEN, "{}", line.text);
+                if i > 0 {
+                    assert_eq!(line.offset, lines[i - 1].offset + LINE_LEN);
+                }
+            }
+            reads += 1;
+        }
+        writer.join().unwrap();
+
+        let lines = tail(&path, None, 64 * 1024, 1).unwrap();
+        assert_eq!(texts(&lines), [format!("line {:06}", LINES - 1).as_str()]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    const MB: usize = 1024 * 1024;
+
+    /// An empty directory of its own for a test.
+    fn log_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("opencode-logs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Writes `size` bytes to `name` in `dir`, last modified `age` ago.
+    fn write_aged(dir: &Path, name: &str, size: usize, age: Duration) {
+        let file = File::create(dir.join(name)).unwrap();
+        file.set_len(size as u64).unwrap();
+        file.set_modified(SystemTime::now() - age).unwrap();
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn hours(n: u64) -> Duration {
+        Duration::from_secs(n * 60 * 60)
+    }
+
+    #[test]
+    fn recognizes_own_log_files() {
+        let cases = [
+            ("opencode-desktop_2025-06-01_12-00-00.log", true),
+            ("opencode-desktop_2025-06-01_12-00-00.2.log", true),
+            ("opencode-sidecar_2025-06-01_12-00-00.1.log.gz", true),
+            ("opencode-desktop_2025-06-01_12-00-00.txt", false),
+            ("opencode-desktop_2025-06-01_12-00-00.log.zip", false),
+            ("opencode_2025-06-01_12-00-00.log", false),
+            ("2025-06-01T120000.log", false),
+            ("notes.log", false),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(is_log_file(name), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn cleanup_leaves_other_files_alone() {
+        let dir = log_dir("foreign");
+        let old = hours(30 * 24);
+        write_aged(&dir, "opencode-desktop_old.log", 10, old);
+        write_aged(&dir, "opencode-sidecar_old.1.log.gz", 10, old);
+        write_aged(&dir, "opencode-desktop_new.log", 10, hours(1));
+        // Someone else's, however old or big
+        write_aged(&dir, "notes.txt", 10, old);
+        write_aged(&dir, "opencode-desktop_old.txt", 10, old);
+        write_aged(&dir, "other-app_old.log", 2 * MB, old);
+        std::fs::create_dir(dir.join("opencode-desktop_dir.log")).unwrap();
+
+        let retention = LogRetention {
+            max_age_days: 7,
+            max_total_mb: 1,
+            max_file_mb: 1,
+        };
+        cleanup(&dir, &retention);
+
+        assert_eq!(
+            names(&dir),
+            [
+                "notes.txt",
+                "opencode-desktop_dir.log",
+                "opencode-desktop_new.log",
+                "opencode-desktop_old.txt",
+                "other-app_old.log",
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cleanup_deletes_oldest_over_total_size() {
+        let dir = log_dir("total");
+        write_aged(&dir, "opencode-desktop_a.log.gz", 400 * 1024, hours(3));
+        write_aged(&dir, "opencode-desktop_b.log.gz", 400 * 1024, hours(1));
+        write_aged(&dir, "opencode-sidecar_c.log", 400 * 1024, hours(2));
+        write_aged(&dir, "opencode-sidecar_d.log", 10, hours(4));
+
+        let retention = LogRetention {
+            max_age_days: 7,
+            max_total_mb: 1,
+            max_file_mb: 1,
+        };
+        cleanup(&dir, &retention);
+
+        // Newest first, b and c fit and a doesn't. d is past the limit too, small as it is.
+        assert_eq!(
+            names(&dir),
+            ["opencode-desktop_b.log.gz", "opencode-sidecar_c.log"]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn cleanup_deletes_files_past_max_age() {
+        let dir = log_dir("age");
+        write_aged(&dir, "opencode-desktop_a.log", 10, hours(8 * 24));
+        write_aged(&dir, "opencode-desktop_b.log", 10, hours(6 * 24));
+
+        let retention = LogRetention {
+            max_age_days: 7,
+            ..LogRetention::default()
+        };
+        cleanup(&dir, &retention);
+
+        assert_eq!(names(&dir), ["opencode-desktop_b.log"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotates_at_max_file_size_and_compresses() {
+        let dir = log_dir("rotate");
+        let retention = LogRetention {
+            max_file_mb: 1,
+            ..LogRetention::default()
+        };
+        let mut file =
+            RotatingFile::create(&dir, "opencode-desktop_test".to_string(), retention).unwrap();
+        let current = file.path();
+
+        let chunk = vec![b'x'; MB / 2];
+        file.write_all(&chunk).unwrap();
+        file.write_all(&chunk).unwrap();
+        // Exactly at the limit, still the first part
+        assert_eq!(
+            *current.lock().unwrap(),
+            dir.join("opencode-desktop_test.log")
+        );
+
+        file.write_all(b"next\n").unwrap();
+        file.flush().unwrap();
+        assert_eq!(
+            *current.lock().unwrap(),
+            dir.join("opencode-desktop_test.1.log")
+        );
+        assert_eq!(
+            std::fs::read(dir.join("opencode-desktop_test.1.log")).unwrap(),
+            b"next\n"
+        );
+
+        // Compressed in the background
+        let compressed = dir.join("opencode-desktop_test.log.gz");
+        for _ in 0..100 {
+            if !dir.join("opencode-desktop_test.log").exists() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert!(!dir.join("opencode-desktop_test.log").exists());
+
+        let mut contents = vec![];
+        flate2::read::GzDecoder::new(File::open(&compressed).unwrap())
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents.len(), MB);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -56,6 +56,13 @@ fn configure_display_backend() -> Option<String> {
 }
 
 fn main() {
+    // Started by the login shell to report its environment, see `shell_env`
+    #[cfg(unix)]
+    if opencode_lib::shell_env::dump_requested() {
+        opencode_lib::shell_env::dump();
+        return;
+    }
+
     // Ensure loopback connections are never sent through proxy settings.
     // Some VPNs/proxies set HTTP_PROXY/HTTPS_PROXY/ALL_PROXY without excluding localhost.
     const LOOPBACK: [&str; 3] = ["127.0.0.1", "localhost", "::1"];
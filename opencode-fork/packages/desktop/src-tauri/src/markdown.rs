@@ -3,6 +3,8 @@ use comrak::{
 };
 use std::fmt::Write;
 
+use crate::command_error::CommandError;
+
 create_formatter!(ExternalLinkFormatter, {
     NodeValue::Link(ref nl) => |context, node, entering| {
         let skip = context.options.parse.relaxed_autolinks
@@ -58,6 +60,6 @@ pub fn parse_markdown(input: &str) -> String {
 
 #[tauri::command]
 #[specta::specta]
-pub async fn parse_markdown_command(markdown: String) -> Result<String, String> {
+pub async fn parse_markdown_command(markdown: String) -> Result<String, CommandError> {
     Ok(parse_markdown(&markdown))
 }
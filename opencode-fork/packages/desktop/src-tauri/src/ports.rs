@@ -0,0 +1,187 @@
+use std::net::TcpListener;
+
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    command_error::CommandError,
+    constants::{SETTINGS_STORE, SIDECAR_PORT_KEY},
+};
+
+const HOSTNAME: &str = "127.0.0.1";
+
+#[derive(Clone, Copy, Default, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SidecarPortSetting {
+    /// Any free port picked by the OS.
+    #[default]
+    Auto,
+    Fixed {
+        port: u16,
+    },
+    /// The first free port in `start..=end`.
+    Range {
+        start: u16,
+        end: u16,
+    },
+}
+
+/// Where the sidecar's port comes from, in order of precedence.
+#[derive(Clone, Copy, Debug)]
+pub enum PortSource {
+    /// `OPENCODE_PORT`, at build time or from the environment.
+    Env(u16),
+    Settings(SidecarPortSetting),
+}
+
+fn env_port() -> Option<u16> {
+    option_env!("OPENCODE_PORT")
+        .map(|s| s.to_string())
+        .or_else(|| std::env::var("OPENCODE_PORT").ok())
+        .and_then(|port_str| port_str.parse().ok())
+}
+
+pub fn read_setting(app: &AppHandle) -> SidecarPortSetting {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SIDECAR_PORT_KEY))
+        .and_then(|v| {
+            serde_json::from_value(v)
+                .inspect_err(|e| tracing::warn!("Invalid sidecar port setting: {e}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+pub fn is_free(port: u16) -> bool {
+    TcpListener::bind((HOSTNAME, port)).is_ok()
+}
+
+/// Names the process holding `port`, if it can be found.
+pub fn port_owner(port: u16) -> Option<String> {
+    listeners::get_all()
+        .ok()?
+        .into_iter()
+        .find(|l| l.socket.port() == port)
+        .map(|l| format!("{} (pid {})", l.process.name, l.process.pid))
+}
+
+impl PortSource {
+    pub fn resolve(app: &AppHandle) -> Self {
+        match env_port() {
+            Some(port) => Self::Env(port),
+            None => Self::Settings(read_setting(app)),
+        }
+    }
+
+    /// The single port the sidecar must use, if the user pinned one.
+    pub fn pinned(&self) -> Option<u16> {
+        match self {
+            Self::Env(port) | Self::Settings(SidecarPortSetting::Fixed { port }) => Some(*port),
+            _ => None,
+        }
+    }
+
+    /// Whether the port was left up to the app, which may then skip TCP entirely.
+    pub fn is_auto(&self) -> bool {
+        matches!(self, Self::Settings(SidecarPortSetting::Auto))
+    }
+
+    /// Whether another port may be tried when the chosen one is taken.
+    pub fn can_retry(&self) -> bool {
+        self.pinned().is_none()
+    }
+
+    /// Explains why a pinned port can't be used.
+    pub fn conflict_message(&self, port: u16) -> String {
+        let owner = port_owner(port)
+            .map(|owner| format!(" by {owner}"))
+            .unwrap_or_default();
+
+        match self {
+            Self::Env(_) => format!(
+                "OPENCODE_PORT is set to {port}, but that port is already in use{owner}. Stop the other process or unset OPENCODE_PORT."
+            ),
+            Self::Settings(SidecarPortSetting::Fixed { .. }) => format!(
+                "The server port is set to {port} in settings, but that port is already in use{owner}. Stop the other process or choose a different port."
+            ),
+            Self::Settings(SidecarPortSetting::Range { start, end }) => {
+                format!("No free port is available for the server between {start} and {end}.")
+            }
+            Self::Settings(SidecarPortSetting::Auto) => {
+                format!("Port {port} is already in use{owner}.")
+            }
+        }
+    }
+
+    /// Picks a port that is free right now, skipping ones that have already failed.
+    ///
+    /// This is still only a hint: something else can bind the port before the sidecar does,
+    /// which is why callers retry when the sidecar reports a conflict.
+    pub fn pick(&self, tried: &[u16]) -> Result<u16, String> {
+        match *self {
+            Self::Env(port) | Self::Settings(SidecarPortSetting::Fixed { port }) => {
+                if is_free(port) {
+                    Ok(port)
+                } else {
+                    Err(self.conflict_message(port))
+                }
+            }
+            Self::Settings(SidecarPortSetting::Range { start, end }) => (start..=end)
+                .find(|port| !tried.contains(port) && is_free(*port))
+                .ok_or_else(|| self.conflict_message(start)),
+            Self::Settings(SidecarPortSetting::Auto) => {
+                for _ in 0..10 {
+                    let port = TcpListener::bind((HOSTNAME, 0))
+                        .and_then(|listener| listener.local_addr())
+                        .map_err(|e| format!("Failed to find a free port: {e}"))?
+                        .port();
+
+                    if !tried.contains(&port) {
+                        return Ok(port);
+                    }
+                }
+
+                Err("Failed to find a free port".to_string())
+            }
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_port_setting(app: AppHandle) -> SidecarPortSetting {
+    read_setting(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_sidecar_port_setting(
+    app: AppHandle,
+    setting: SidecarPortSetting,
+) -> Result<(), CommandError> {
+    match setting {
+        SidecarPortSetting::Fixed { port: 0 } => {
+            return Err(CommandError::invalid_input(
+                "port",
+                "Port must be between 1 and 65535",
+            ));
+        }
+        SidecarPortSetting::Range { start, end } if start == 0 || start > end => {
+            return Err(CommandError::invalid_input(
+                "start",
+                format!("Invalid port range {start}-{end}"),
+            ));
+        }
+        _ => {}
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(SIDECAR_PORT_KEY, serde_json::json!(setting));
+    store.save().map_err(CommandError::settings_save)?;
+
+    Ok(())
+}
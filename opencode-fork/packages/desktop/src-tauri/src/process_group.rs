@@ -0,0 +1,524 @@
+//! Linux process-group supervision for reliable sidecar cleanup.
+//!
+//! Linux has no equivalent of `JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE`, so the sidecar is started
+//! in its own session (and therefore its own process group), together with a small watchdog
+//! process forked off just before `exec`. The watchdog blocks on a pipe whose only write end
+//! lives in the desktop app. When the app goes away for any reason, including a crash or
+//! `kill -9`, the kernel closes that end, the watchdog reads EOF and kills the whole group.
+//!
+//! This is used instead of `PR_SET_PDEATHSIG`, which fires when the *thread* that forked the
+//! child exits rather than the process. Sidecars are spawned from tokio workers, which may be
+//! torn down while the app keeps running.
+//!
+//! Tool subprocesses that start their own process group escape `kill(-pgid)`, so when the
+//! cgroup v2 hierarchy is writable the sidecar is also moved into a dedicated child cgroup and
+//! the watchdog writes to its `cgroup.kill` as well. The same cgroup carries the sidecar's
+//! [resource limits](crate::resource_limits).
+
+use std::{
+    fs,
+    io::{Error, Result},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        unix::process::{CommandExt, ExitStatusExt},
+    },
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+use tauri_plugin_shell::process::{CommandEvent, TerminatedPayload};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    sync::mpsc,
+};
+
+use crate::resource_limits::{self, Enforcement, ResourceLimits};
+
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// Handle to a sidecar running in its own process group.
+pub struct SidecarChild {
+    pid: u32,
+    cgroup: Option<PathBuf>,
+    enforcement: Enforcement,
+}
+
+impl SidecarChild {
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// The cgroup the sidecar runs in, until it and all its children have exited.
+    pub fn cgroup(&self) -> Option<&Path> {
+        self.cgroup.as_deref()
+    }
+
+    pub fn enforcement(&self) -> Enforcement {
+        self.enforcement
+    }
+
+    /// Kills the sidecar along with everything else in its process group.
+    pub fn kill(self) -> Result<()> {
+        // SAFETY: kill has no memory safety requirements. The sidecar called setsid, so its
+        // pid is also its process group id.
+        if unsafe { libc::kill(-(self.pid as libc::pid_t), libc::SIGKILL) } == 0 {
+            Ok(())
+        } else {
+            Err(Error::last_os_error())
+        }
+    }
+}
+
+/// A child cgroup the sidecar tree is moved into, so it can be killed as a unit.
+struct SidecarCgroup {
+    path: PathBuf,
+    procs: fs::File,
+    kill: Option<fs::File>,
+    /// Whether the configured resource limits were applied to it.
+    limited: bool,
+}
+
+impl SidecarCgroup {
+    fn create(limits: &ResourceLimits) -> Result<Self> {
+        // cgroup v2 has a single "0::<path>" entry
+        let current = fs::read_to_string("/proc/self/cgroup")?
+            .lines()
+            .find_map(|line| line.strip_prefix("0::").map(str::to_string))
+            .ok_or_else(|| Error::other("cgroup v2 is not mounted"))?;
+        let current = PathBuf::from("/sys/fs/cgroup").join(current.trim_start_matches('/'));
+
+        // Limits need their controllers enabled in the parent cgroup, which the kernel refuses
+        // for one that holds processes itself, like the app's. Its own parent (for example a
+        // delegated `app.slice`) only holds other cgroups, so the limited cgroup goes there.
+        if limits.is_set()
+            && let Some(parent) = current.parent()
+        {
+            match Self::create_in(parent).and_then(|mut cgroup| {
+                cgroup.apply(parent, limits)?;
+                Ok(cgroup)
+            }) {
+                Ok(cgroup) => return Ok(cgroup),
+                Err(e) => tracing::debug!("Sidecar cgroup limits unavailable: {e}"),
+            }
+        }
+
+        Self::create_in(&current)
+    }
+
+    fn create_in(parent: &Path) -> Result<Self> {
+        let path = parent.join(format!("opencode-sidecar-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&path)?;
+
+        let open = |name: &str| fs::OpenOptions::new().write(true).open(path.join(name));
+
+        let procs = match open("cgroup.procs") {
+            Ok(procs) => procs,
+            Err(e) => {
+                let _ = fs::remove_dir(&path);
+                return Err(e);
+            }
+        };
+        // cgroup.kill needs Linux 5.14, the process group kill still applies without it
+        let kill = open("cgroup.kill").ok();
+
+        Ok(Self {
+            path,
+            procs,
+            kill,
+            limited: false,
+        })
+    }
+
+    fn apply(&mut self, parent: &Path, limits: &ResourceLimits) -> Result<()> {
+        let enable = |controller: &str| {
+            fs::write(
+                parent.join("cgroup.subtree_control"),
+                format!("+{controller}"),
+            )
+        };
+
+        if let Some(memory_mb) = limits.memory_mb {
+            enable("memory")?;
+            fs::write(
+                self.path.join("memory.max"),
+                (u64::from(memory_mb) << 20).to_string(),
+            )?;
+            // Otherwise the limit only moves the excess to swap
+            let _ = fs::write(self.path.join("memory.swap.max"), "0");
+        }
+        if let Some(cpu_percent) = limits.cpu_percent {
+            enable("cpu")?;
+            let quota = u64::from(cpu_percent) * CPU_PERIOD_US / 100;
+            fs::write(
+                self.path.join("cpu.max"),
+                format!("{quota} {CPU_PERIOD_US}"),
+            )?;
+        }
+        if let Some(max_processes) = limits.max_processes {
+            enable("pids")?;
+            fs::write(self.path.join("pids.max"), max_processes.to_string())?;
+        }
+
+        self.limited = true;
+        Ok(())
+    }
+
+    fn contains(&self, pid: u32) -> bool {
+        fs::read_to_string(self.path.join("cgroup.procs"))
+            .is_ok_and(|procs| procs.lines().any(|line| line.trim() == pid.to_string()))
+    }
+}
+
+/// `RLIMIT_DATA` and `RLIMIT_NPROC` values standing in for cgroup limits.
+#[derive(Clone, Copy, Default)]
+struct Rlimits {
+    data: Option<libc::rlim_t>,
+    nproc: Option<libc::rlim_t>,
+}
+
+impl Rlimits {
+    fn new(limits: &ResourceLimits) -> Self {
+        Self {
+            data: limits.memory_mb.map(|mb| u64::from(mb) << 20),
+            // RLIMIT_NPROC counts all of the user's tasks, so only allow this many more
+            nproc: limits
+                .max_processes
+                .map(|n| resource_limits::user_task_count() + u64::from(n)),
+        }
+    }
+
+    fn values(&self) -> [(libc::__rlimit_resource_t, Option<libc::rlim_t>); 2] {
+        [
+            (libc::RLIMIT_DATA, self.data),
+            (libc::RLIMIT_NPROC, self.nproc),
+        ]
+    }
+
+    /// Applies the limits to the calling process. Async-signal-safe.
+    fn apply(&self) -> Result<()> {
+        for (resource, limit) in self.values() {
+            let Some(limit) = limit else { continue };
+            let rlimit = libc::rlimit {
+                rlim_cur: limit,
+                rlim_max: limit,
+            };
+            // SAFETY: rlimit is a valid struct for the duration of the call.
+            if unsafe { libc::setrlimit(resource, &rlimit) } == -1 {
+                return Err(Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the limits to an already running process.
+    fn apply_to(&self, pid: u32) -> Result<()> {
+        for (resource, limit) in self.values() {
+            let Some(limit) = limit else { continue };
+            let rlimit = libc::rlimit {
+                rlim_cur: limit,
+                rlim_max: limit,
+            };
+            // SAFETY: rlimit is a valid struct for the duration of the call, and a null old
+            // limit is allowed.
+            if unsafe { libc::prlimit(pid as libc::pid_t, resource, &rlimit, std::ptr::null_mut()) }
+                == -1
+            {
+                return Err(Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SidecarCgroup {
+    fn drop(&mut self) {
+        // Only succeeds once every process in it has exited
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+fn pipe() -> Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: fds is a valid buffer for two descriptors, which we take ownership of on success.
+    unsafe {
+        if libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])))
+    }
+}
+
+/// Body of the forked watchdog. Runs between fork and exec of a multi-threaded process, so
+/// only async-signal-safe calls are allowed.
+unsafe fn watchdog(watch_fd: RawFd, kill_fd: RawFd) -> ! {
+    unsafe {
+        // Keep only the pipe and cgroup.kill, as fd 0 and 1. Anything else inherited, like the
+        // sidecar's stdout or std's exec status pipe, would otherwise be held open forever.
+        libc::dup2(watch_fd, 0);
+        if kill_fd >= 0 {
+            libc::dup2(kill_fd, 1);
+        } else {
+            libc::close(1);
+        }
+        if libc::syscall(libc::SYS_close_range, 2u32, u32::MAX, 0u32) != 0 {
+            for fd in 2..65536 {
+                libc::close(fd);
+            }
+        }
+
+        let mut buf = [0u8; 1];
+        loop {
+            let n = libc::read(0, buf.as_mut_ptr().cast(), 1);
+            if n == 0 || (n < 0 && *libc::__errno_location() != libc::EINTR) {
+                break;
+            }
+        }
+
+        if kill_fd >= 0 {
+            libc::write(1, b"1".as_ptr().cast(), 1);
+        }
+        // We're part of the group too, so this doesn't return
+        libc::kill(0, libc::SIGKILL);
+        libc::_exit(0)
+    }
+}
+
+fn forward_lines<R: AsyncRead + Unpin + Send + 'static>(
+    reader: R,
+    tx: mpsc::Sender<CommandEvent>,
+    event: fn(Vec<u8>) -> CommandEvent,
+) {
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).split(b'\n');
+        while let Ok(Some(line)) = lines.next_segment().await {
+            if tx.send(event(line)).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Spawns `command` in a new session that is killed as a whole when the app exits, emitting
+/// the same events as `tauri_plugin_shell`'s `Command::spawn`.
+pub fn spawn(
+    mut command: Command,
+    limits: &ResourceLimits,
+) -> Result<(mpsc::Receiver<CommandEvent>, SidecarChild)> {
+    let (watch_end, app_end) = pipe()?;
+
+    let cgroup = SidecarCgroup::create(limits)
+        .inspect_err(|e| {
+            tracing::debug!("Sidecar cgroup unavailable, using process group only: {e}")
+        })
+        .ok();
+
+    let rlimits = if limits.is_set() && !cgroup.as_ref().is_some_and(|c| c.limited) {
+        Rlimits::new(limits)
+    } else {
+        Rlimits::default()
+    };
+
+    let watch_fd = watch_end.as_raw_fd();
+    let procs_fd = cgroup.as_ref().map_or(-1, |c| c.procs.as_raw_fd());
+    let kill_fd = cgroup
+        .as_ref()
+        .and_then(|c| c.kill.as_ref())
+        .map_or(-1, |f| f.as_raw_fd());
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // SAFETY: the closure runs between fork and exec and only makes async-signal-safe calls.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setsid() == -1 {
+                return Err(Error::last_os_error());
+            }
+
+            rlimits.apply()?;
+
+            // Best effort, "0" moves the calling process
+            if procs_fd >= 0 {
+                libc::write(procs_fd, b"0".as_ptr().cast(), 1);
+            }
+
+            match libc::fork() {
+                -1 => Err(Error::last_os_error()),
+                0 => watchdog(watch_fd, kill_fd),
+                _ => Ok(()),
+            }
+        });
+    }
+
+    let mut child = tokio::process::Command::from(command).spawn()?;
+    // Only the watchdog needs the read end
+    drop(watch_end);
+
+    let pid = child
+        .id()
+        .ok_or_else(|| Error::other("Sidecar exited before its pid was read"))?;
+
+    let mut enforcement = match (&cgroup, limits.is_set()) {
+        (_, false) => Enforcement::None,
+        (Some(c), true) if c.limited => Enforcement::Cgroup,
+        _ => Enforcement::Rlimit,
+    };
+
+    // Moving into the cgroup can fail without stopping the spawn
+    if enforcement == Enforcement::Cgroup && !cgroup.as_ref().is_some_and(|c| c.contains(pid)) {
+        tracing::warn!("Sidecar isn't in its cgroup, falling back to rlimits");
+        if let Err(e) = Rlimits::new(limits).apply_to(pid) {
+            tracing::warn!("Failed to apply sidecar rlimits: {e}");
+        }
+        enforcement = Enforcement::Rlimit;
+    }
+
+    tracing::info!(
+        pid,
+        cgroup = cgroup.is_some(),
+        ?enforcement,
+        "Spawned sidecar in its own process group"
+    );
+
+    let cgroup_path = cgroup.as_ref().map(|c| c.path.clone());
+
+    let (tx, rx) = mpsc::channel(256);
+
+    if let Some(stdout) = child.stdout.take() {
+        forward_lines(stdout, tx.clone(), CommandEvent::Stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward_lines(stderr, tx.clone(), CommandEvent::Stderr);
+    }
+
+    tokio::spawn(async move {
+        let payload = match child.wait().await {
+            Ok(status) => TerminatedPayload {
+                code: status.code(),
+                signal: status.signal(),
+            },
+            Err(e) => {
+                let _ = tx.send(CommandEvent::Error(e.to_string())).await;
+                TerminatedPayload {
+                    code: None,
+                    signal: None,
+                }
+            }
+        };
+
+        // Closing our end makes the watchdog kill anything the sidecar left behind
+        drop(app_end);
+        let _ = tx.send(CommandEvent::Terminated(payload)).await;
+
+        if let Some(cgroup) = cgroup {
+            // Give the watchdog a moment to empty the cgroup so it can be removed
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            drop(cgroup);
+        }
+    });
+
+    Ok((
+        rx,
+        SidecarChild {
+            pid,
+            cgroup: cgroup_path,
+            enforcement,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader},
+        time::Instant,
+    };
+
+    const HELPER_ENV: &str = "OPENCODE_PROCESS_GROUP_TEST_HELPER";
+
+    fn is_gone(pid: u32) -> bool {
+        // Zombies count as gone, the container's init may never reap them
+        match fs::read_to_string(format!("/proc/{pid}/stat")) {
+            Ok(stat) => stat
+                .rsplit_once(')')
+                .is_some_and(|(_, rest)| rest.trim_start().starts_with('Z')),
+            Err(_) => true,
+        }
+    }
+
+    /// Stands in for the desktop app when re-executed by `children_die_with_parent`: spawns a
+    /// sidecar with a background grandchild, prints both pids and waits to be killed.
+    #[test]
+    fn parent_helper() {
+        if std::env::var_os(HELPER_ENV).is_none() {
+            return;
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let mut command = Command::new("sh");
+            command.args(["-c", "sleep 1000 & echo $!; exec sleep 1000"]);
+
+            let (mut rx, child) = spawn(command, &ResourceLimits::default()).unwrap();
+            println!("child={}", child.pid());
+
+            while let Some(event) = rx.recv().await {
+                if let CommandEvent::Stdout(line) = event {
+                    println!("grandchild={}", String::from_utf8_lossy(&line).trim());
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn children_die_with_parent() {
+        let mut parent = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "process_group::tests::parent_helper",
+                "--exact",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(HELPER_ENV, "1")
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut pids = vec![];
+        let mut lines = BufReader::new(parent.stdout.take().unwrap()).lines();
+        while pids.len() < 2 {
+            let line = lines.next().expect("helper exited early").unwrap();
+            if let Some(pid) = line
+                .strip_prefix("child=")
+                .or_else(|| line.strip_prefix("grandchild="))
+            {
+                pids.push(pid.parse::<u32>().unwrap());
+            }
+        }
+
+        assert!(
+            pids.iter().all(|&pid| !is_gone(pid)),
+            "children should be running"
+        );
+
+        parent.kill().unwrap();
+        parent.wait().unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !pids.iter().all(|&pid| is_gone(pid)) {
+            assert!(
+                Instant::now() < deadline,
+                "children outlived their parent: {pids:?}"
+            );
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+}
@@ -0,0 +1,613 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    command_error::{CommandError, CommandErrorKind},
+    constants::{DEFAULT_SERVER_URL_KEY, SERVER_PROFILES_KEY, SETTINGS_STORE},
+    server, tls,
+};
+
+#[derive(Clone, Serialize, Deserialize, specta::Type, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ServerAuth {
+    Basic { username: String, password: String },
+    Bearer { token: String },
+}
+
+impl ServerAuth {
+    /// The password or token.
+    pub fn secret(&self) -> &str {
+        match self {
+            Self::Basic { password, .. } => password,
+            Self::Bearer { token } => token,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTls {
+    /// Extra PEM-encoded root certificates to trust for this server.
+    #[serde(default)]
+    pub ca_certificates: Vec<String>,
+    /// Hex-encoded SHA-256 fingerprint of the server's leaf certificate.
+    #[serde(default)]
+    pub pinned_fingerprint: Option<String>,
+    /// PEM-encoded client certificate chain for mutual TLS.
+    #[serde(default)]
+    pub client_certificate: Option<String>,
+    /// PEM-encoded private key matching `client_certificate`.
+    #[serde(default)]
+    pub client_key: Option<String>,
+}
+
+impl ServerTls {
+    /// Whether anything differs from the default webpki roots, i.e. the plain HTTP plugin
+    /// client can't be used for this server.
+    pub fn is_custom(&self) -> bool {
+        !self.ca_certificates.is_empty()
+            || self
+                .pinned_fingerprint
+                .as_deref()
+                .is_some_and(|v| !v.is_empty())
+            || self.client_certificate.is_some()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProfile {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub auth: Option<ServerAuth>,
+    #[serde(default)]
+    pub tls: Option<ServerTls>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+#[derive(Clone, Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProfileInput {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub auth: Option<ServerAuth>,
+    #[serde(default)]
+    pub tls: Option<ServerTls>,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+impl ServerProfile {
+    /// Builds a throwaway profile for a URL that didn't come from the profile list,
+    /// e.g. `server.port` in the OpenCode config.
+    pub fn from_url(name: &str, url: String) -> Self {
+        Self {
+            id: String::new(),
+            name: name.to_string(),
+            url,
+            auth: None,
+            tls: None,
+            is_default: false,
+        }
+    }
+
+    fn new(input: ServerProfileInput) -> Self {
+        let mut profile = Self::from_url("", String::new());
+        profile.id = uuid::Uuid::new_v4().to_string();
+        profile.apply(input);
+        profile
+    }
+
+    fn apply(&mut self, input: ServerProfileInput) {
+        self.name = input.name.trim().to_string();
+        self.url = input.url.trim().trim_end_matches('/').to_string();
+        self.auth = input.auth;
+        self.tls = input.tls;
+        self.is_default = input.is_default;
+    }
+}
+
+fn validate(input: &ServerProfileInput) -> Result<(), CommandError> {
+    if input.name.trim().is_empty() {
+        return Err(CommandError::invalid_input(
+            "name",
+            "Profile name cannot be empty",
+        ));
+    }
+
+    let url = reqwest::Url::parse(input.url.trim())
+        .map_err(|e| CommandError::invalid_url(&input.url, e))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(CommandError::new(
+            CommandErrorKind::InvalidUrl {
+                url: input.url.clone(),
+            },
+            format!(
+                "Unsupported URL scheme '{}', expected http or https",
+                url.scheme()
+            ),
+        ));
+    }
+
+    if let Some(tls) = &input.tls {
+        if tls.client_certificate.is_some() != tls.client_key.is_some() {
+            return Err(CommandError::invalid_input(
+                "tls",
+                "Client certificate and key must be provided together",
+            ));
+        }
+
+        // Surfaces unparseable PEM now rather than as a connection failure later
+        tls::client_builder(Some(tls)).map_err(|e| CommandError::invalid_input("tls", e))?;
+    }
+
+    Ok(())
+}
+
+pub fn read(app: &AppHandle) -> Result<Vec<ServerProfile>, CommandError> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    let (profiles, migrated) = load(
+        store.get(SERVER_PROFILES_KEY),
+        store.get(DEFAULT_SERVER_URL_KEY),
+    )?;
+    if migrated {
+        store.delete(DEFAULT_SERVER_URL_KEY);
+        write(app, &profiles)?;
+    }
+
+    Ok(profiles)
+}
+
+/// Parses the saved profiles, or migrates `legacy_url` if none were saved yet. Also returns
+/// whether they were migrated, in which case they still have to be saved.
+fn load(
+    saved: Option<Value>,
+    legacy_url: Option<Value>,
+) -> Result<(Vec<ServerProfile>, bool), CommandError> {
+    let Some(saved) = saved else {
+        let url = legacy_url.and_then(|v| v.as_str().map(String::from));
+        return Ok((migrate_legacy_url(url), true));
+    };
+
+    let profiles = serde_json::from_value(saved).map_err(|e| {
+        CommandError::new(
+            CommandErrorKind::Settings,
+            format!("Failed to parse server profiles: {}", e),
+        )
+    })?;
+
+    Ok((profiles, false))
+}
+
+fn write(app: &AppHandle, profiles: &[ServerProfile]) -> Result<(), CommandError> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(SERVER_PROFILES_KEY, json!(profiles));
+    store.save().map_err(CommandError::settings_save)?;
+
+    Ok(())
+}
+
+/// Turns the single `defaultServerUrl` from older versions into a default profile.
+fn migrate_legacy_url(url: Option<String>) -> Vec<ServerProfile> {
+    let Some(url) = url else {
+        return vec![];
+    };
+
+    tracing::info!(%url, "Migrating default server URL into a profile");
+
+    let name = reqwest::Url::parse(&url)
+        .ok()
+        .and_then(|u| u.host_str().map(String::from))
+        .unwrap_or_else(|| "Default".to_string());
+
+    vec![ServerProfile {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        url,
+        auth: None,
+        tls: None,
+        is_default: true,
+    }]
+}
+
+/// Only one profile can be the default, so the last one marked wins.
+fn ensure_single_default(profiles: &mut [ServerProfile], default_id: &str) {
+    for profile in profiles.iter_mut() {
+        if profile.id != default_id {
+            profile.is_default = false;
+        }
+    }
+}
+
+pub fn get_default(app: &AppHandle) -> Option<ServerProfile> {
+    read(app)
+        .inspect_err(|e| tracing::warn!("Failed to read server profiles: {e}"))
+        .ok()?
+        .into_iter()
+        .find(|p| p.is_default)
+}
+
+/// Marks the profile with the given URL as default, creating one if needed.
+/// Passing `None` clears the default so the local sidecar is used.
+pub fn set_default_url(app: &AppHandle, url: Option<String>) -> Result<(), CommandError> {
+    let mut profiles = read(app)?;
+    select_default_url(&mut profiles, url)?;
+    write(app, &profiles)
+}
+
+fn select_default_url(
+    profiles: &mut Vec<ServerProfile>,
+    url: Option<String>,
+) -> Result<(), CommandError> {
+    let Some(url) = url else {
+        for profile in profiles.iter_mut() {
+            profile.is_default = false;
+        }
+        return Ok(());
+    };
+
+    let url = url.trim().trim_end_matches('/').to_string();
+
+    let id = match profiles.iter().find(|p| p.url == url) {
+        Some(profile) => profile.id.clone(),
+        None => {
+            let input = ServerProfileInput {
+                name: reqwest::Url::parse(&url)
+                    .ok()
+                    .and_then(|u| u.host_str().map(String::from))
+                    .unwrap_or_else(|| url.clone()),
+                url,
+                auth: None,
+                tls: None,
+                is_default: true,
+            };
+            validate(&input)?;
+
+            let profile = ServerProfile::new(input);
+            let id = profile.id.clone();
+            profiles.push(profile);
+            id
+        }
+    };
+
+    for profile in profiles.iter_mut() {
+        profile.is_default = profile.id == id;
+    }
+
+    Ok(())
+}
+
+/// Makes the profile for `url` the default, creating it if needed and replacing its
+/// credentials with `auth`.
+pub fn attach(
+    app: &AppHandle,
+    name: &str,
+    url: &str,
+    auth: Option<ServerAuth>,
+) -> Result<ServerProfile, CommandError> {
+    let mut profiles = read(app)?;
+    let attached = attach_to(&mut profiles, name, url, auth)?;
+    write(app, &profiles)?;
+
+    Ok(attached)
+}
+
+fn attach_to(
+    profiles: &mut Vec<ServerProfile>,
+    name: &str,
+    url: &str,
+    auth: Option<ServerAuth>,
+) -> Result<ServerProfile, CommandError> {
+    let url = url.trim().trim_end_matches('/');
+
+    let input = ServerProfileInput {
+        name: name.to_string(),
+        url: url.to_string(),
+        auth,
+        tls: None,
+        is_default: true,
+    };
+    validate(&input)?;
+
+    let attached = match profiles.iter_mut().find(|p| p.url == url) {
+        Some(existing) => {
+            existing.auth = input.auth;
+            existing.is_default = true;
+            existing.clone()
+        }
+        None => {
+            let created = ServerProfile::new(input);
+            profiles.push(created.clone());
+            created
+        }
+    };
+
+    ensure_single_default(profiles, &attached.id);
+
+    Ok(attached)
+}
+
+/// Saves a certificate fingerprint the user chose to trust on first use.
+pub fn pin_certificate(app: &AppHandle, id: &str, fingerprint: &str) -> Result<(), CommandError> {
+    let mut profiles = read(app)?;
+
+    let profile = profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| CommandError::profile_not_found(id))?;
+    profile.tls.get_or_insert_default().pinned_fingerprint = Some(fingerprint.to_string());
+
+    write(app, &profiles)
+}
+
+fn insert(
+    profiles: &mut Vec<ServerProfile>,
+    input: ServerProfileInput,
+) -> Result<ServerProfile, CommandError> {
+    validate(&input)?;
+
+    let created = ServerProfile::new(input);
+
+    if created.is_default {
+        ensure_single_default(profiles, &created.id);
+    }
+
+    profiles.push(created.clone());
+
+    Ok(created)
+}
+
+fn update(
+    profiles: &mut [ServerProfile],
+    id: &str,
+    input: ServerProfileInput,
+) -> Result<ServerProfile, CommandError> {
+    validate(&input)?;
+
+    let existing = profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| CommandError::profile_not_found(id))?;
+    existing.apply(input);
+    let updated = existing.clone();
+
+    if updated.is_default {
+        ensure_single_default(profiles, &updated.id);
+    }
+
+    Ok(updated)
+}
+
+fn remove(profiles: &mut Vec<ServerProfile>, id: &str) -> Result<(), CommandError> {
+    let len = profiles.len();
+    profiles.retain(|p| p.id != id);
+    if profiles.len() == len {
+        return Err(CommandError::profile_not_found(id));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_server_profiles(app: AppHandle) -> Result<Vec<ServerProfile>, CommandError> {
+    read(&app)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_server_profile(
+    app: AppHandle,
+    profile: ServerProfileInput,
+) -> Result<ServerProfile, CommandError> {
+    let mut profiles = read(&app)?;
+    let created = insert(&mut profiles, profile)?;
+    write(&app, &profiles)?;
+
+    tracing::info!(id = %created.id, name = %created.name, "Created server profile");
+
+    Ok(created)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_server_profile(
+    app: AppHandle,
+    id: String,
+    profile: ServerProfileInput,
+) -> Result<ServerProfile, CommandError> {
+    let mut profiles = read(&app)?;
+    let updated = update(&mut profiles, &id, profile)?;
+    write(&app, &profiles)?;
+
+    tracing::info!(id = %updated.id, name = %updated.name, "Updated server profile");
+
+    Ok(updated)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_server_profile(app: AppHandle, id: String) -> Result<(), CommandError> {
+    let mut profiles = read(&app)?;
+    remove(&mut profiles, &id)?;
+    write(&app, &profiles)?;
+
+    tracing::info!(%id, "Deleted server profile");
+
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn test_server_profile(
+    profile: ServerProfileInput,
+) -> Result<server::HealthStatus, CommandError> {
+    validate(&profile)?;
+
+    Ok(server::probe_health(
+        profile.url.trim(),
+        profile.auth.as_ref(),
+        profile.tls.as_ref(),
+    )
+    .await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(name: &str, url: &str, is_default: bool) -> ServerProfileInput {
+        ServerProfileInput {
+            name: name.to_string(),
+            url: url.to_string(),
+            auth: None,
+            tls: None,
+            is_default,
+        }
+    }
+
+    fn defaults(profiles: &[ServerProfile]) -> Vec<&str> {
+        profiles
+            .iter()
+            .filter(|p| p.is_default)
+            .map(|p| p.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn migrates_legacy_url_into_default_profile() {
+        let (profiles, migrated) = load(None, Some(json!("https://example.com:4096"))).unwrap();
+
+        assert!(migrated);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].name, "example.com");
+        assert_eq!(profiles[0].url, "https://example.com:4096");
+        assert!(profiles[0].is_default);
+
+        let (profiles, _) = load(None, Some(json!("not a url"))).unwrap();
+        assert_eq!(profiles[0].name, "Default");
+
+        let (profiles, migrated) = load(None, None).unwrap();
+        assert!(migrated);
+        assert!(profiles.is_empty());
+    }
+
+    #[test]
+    fn migration_runs_only_once() {
+        let legacy = json!("http://localhost:4096");
+        let (migrated, _) = load(None, Some(legacy.clone())).unwrap();
+
+        // A legacy key that survived, e.g. because saving failed halfway, is ignored
+        let (profiles, migrated_again) = load(Some(json!(migrated)), Some(legacy)).unwrap();
+
+        assert!(!migrated_again);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].id, migrated[0].id);
+
+        let (profiles, migrated_again) = load(Some(json!([])), None).unwrap();
+        assert!(!migrated_again);
+        assert!(profiles.is_empty());
+    }
+
+    #[test]
+    fn keeps_single_default_on_create() {
+        let mut profiles = vec![];
+        insert(&mut profiles, input("a", "http://a", true)).unwrap();
+        insert(&mut profiles, input("b", "http://b", false)).unwrap();
+        assert_eq!(defaults(&profiles), ["a"]);
+
+        insert(&mut profiles, input("c", "http://c", true)).unwrap();
+        assert_eq!(defaults(&profiles), ["c"]);
+    }
+
+    #[test]
+    fn keeps_single_default_on_update() {
+        let mut profiles = vec![];
+        insert(&mut profiles, input("a", "http://a", true)).unwrap();
+        let b = insert(&mut profiles, input("b", "http://b", false)).unwrap();
+
+        update(&mut profiles, &b.id, input("b", "http://b", true)).unwrap();
+        assert_eq!(defaults(&profiles), ["b"]);
+
+        update(&mut profiles, &b.id, input("b", "http://b", false)).unwrap();
+        assert!(defaults(&profiles).is_empty());
+
+        let err = update(&mut profiles, "missing", input("x", "http://x", true)).unwrap_err();
+        assert!(matches!(err.kind, CommandErrorKind::ProfileNotFound { .. }));
+        assert!(defaults(&profiles).is_empty());
+    }
+
+    #[test]
+    fn keeps_single_default_on_delete() {
+        let mut profiles = vec![];
+        let a = insert(&mut profiles, input("a", "http://a", true)).unwrap();
+        let b = insert(&mut profiles, input("b", "http://b", false)).unwrap();
+
+        remove(&mut profiles, &b.id).unwrap();
+        assert_eq!(defaults(&profiles), ["a"]);
+
+        // Without a default the local sidecar is used again
+        remove(&mut profiles, &a.id).unwrap();
+        assert!(profiles.is_empty());
+
+        let err = remove(&mut profiles, &a.id).unwrap_err();
+        assert!(matches!(err.kind, CommandErrorKind::ProfileNotFound { .. }));
+    }
+
+    #[test]
+    fn keeps_single_default_on_set_default_url() {
+        let mut profiles = vec![];
+        insert(&mut profiles, input("a", "http://a", true)).unwrap();
+        insert(&mut profiles, input("b", "http://b", false)).unwrap();
+
+        select_default_url(&mut profiles, Some("http://b/".to_string())).unwrap();
+        assert_eq!(defaults(&profiles), ["b"]);
+        assert_eq!(profiles.len(), 2);
+
+        select_default_url(&mut profiles, Some("http://localhost:4096".to_string())).unwrap();
+        assert_eq!(defaults(&profiles), ["localhost"]);
+        assert_eq!(profiles.len(), 3);
+
+        select_default_url(&mut profiles, None).unwrap();
+        assert!(defaults(&profiles).is_empty());
+        assert_eq!(profiles.len(), 3);
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let field = |input: ServerProfileInput| match validate(&input).unwrap_err().kind {
+            CommandErrorKind::InvalidInput { field } => field,
+            CommandErrorKind::InvalidUrl { .. } => "url".to_string(),
+            kind => panic!("unexpected error kind {kind:?}"),
+        };
+
+        assert_eq!(field(input(" ", "http://a", false)), "name");
+        assert_eq!(field(input("a", "not a url", false)), "url");
+        assert_eq!(field(input("a", "ftp://a", false)), "url");
+
+        let mut cert_only = input("a", "http://a", false);
+        cert_only.tls = Some(ServerTls {
+            client_certificate: Some("cert".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(field(cert_only), "tls");
+
+        let mut profiles = vec![];
+        assert!(insert(&mut profiles, input("a", "ftp://a", true)).is_err());
+        assert!(select_default_url(&mut profiles, Some("ftp://a".to_string())).is_err());
+        assert!(profiles.is_empty());
+    }
+}
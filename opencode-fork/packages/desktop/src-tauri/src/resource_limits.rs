@@ -0,0 +1,382 @@
+//! Memory, CPU and process-count limits for the local sidecar and the tools it runs.
+//!
+//! Limits are only enforced on Linux. When the user's cgroup v2 tree is delegated, as systemd
+//! does for desktop sessions, the sidecar gets its own cgroup next to the app's with
+//! `memory.max`, `cpu.max` and `pids.max`, which cover everything it spawns. Otherwise the
+//! sidecar falls back to rlimits: `RLIMIT_DATA` caps each process separately, `RLIMIT_NPROC`
+//! counts every process of the user, and CPU isn't limited at all.
+
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_store::StoreExt;
+
+use crate::{
+    command_error::CommandError,
+    constants::{SETTINGS_STORE, SIDECAR_RESOURCE_LIMITS_KEY},
+};
+
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, specta::Type, Debug,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimits {
+    pub memory_mb: Option<u32>,
+    /// Share of one core, e.g. 200 for two full cores.
+    pub cpu_percent: Option<u32>,
+    pub max_processes: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn is_set(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Enforcement {
+    /// No limits are configured, or the platform doesn't support them.
+    None,
+    Cgroup,
+    /// Per-process rlimits only, the CPU limit isn't applied.
+    Rlimit,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitedResource {
+    Memory,
+    Cpu,
+    Processes,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub enforcement: Enforcement,
+    pub limits: ResourceLimits,
+    pub memory_mb: u32,
+    /// Share of one core since the previous sample.
+    pub cpu_percent: f64,
+    pub processes: u32,
+    /// How often each limit was hit. Only known when enforced through a cgroup.
+    pub memory_limit_hits: Option<u32>,
+    pub oom_kills: Option<u32>,
+    pub cpu_throttled: Option<u32>,
+    pub process_limit_hits: Option<u32>,
+}
+
+/// Emitted when the sidecar runs into one of its limits, with the total number of hits so far.
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, tauri_specta::Event, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimitHit {
+    pub resource: LimitedResource,
+    pub count: u32,
+}
+
+#[derive(Default)]
+pub struct ResourceMonitorState {
+    /// The latest sample, with the pid of the sidecar it's from.
+    usage: Mutex<Option<(u32, ResourceUsage)>>,
+}
+
+pub fn read(app: &AppHandle) -> ResourceLimits {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SIDECAR_RESOURCE_LIMITS_KEY))
+        .and_then(|v| {
+            serde_json::from_value(v)
+                .inspect_err(|e| tracing::warn!("Invalid sidecar resource limits: {e}"))
+                .ok()
+        })
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_resource_limits(app: AppHandle) -> ResourceLimits {
+    read(&app)
+}
+
+/// Takes effect the next time the sidecar starts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_sidecar_resource_limits(
+    app: AppHandle,
+    limits: ResourceLimits,
+) -> Result<(), CommandError> {
+    let zero = [
+        ("memoryMb", limits.memory_mb),
+        ("cpuPercent", limits.cpu_percent),
+        ("maxProcesses", limits.max_processes),
+    ]
+    .into_iter()
+    .find(|(_, limit)| *limit == Some(0));
+    if let Some((field, _)) = zero {
+        return Err(CommandError::invalid_input(
+            field,
+            "Resource limits must be greater than zero",
+        ));
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(SIDECAR_RESOURCE_LIMITS_KEY, serde_json::json!(limits));
+    store.save().map_err(CommandError::settings_save)?;
+
+    Ok(())
+}
+
+/// The latest usage sample of the local sidecar, if one is running and is being monitored.
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_resource_usage(state: State<'_, ResourceMonitorState>) -> Option<ResourceUsage> {
+    state
+        .usage
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|(_, usage)| usage.clone())
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::*;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    };
+
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+    use tauri_specta::Event;
+
+    use super::*;
+    use crate::cli::SidecarChild;
+
+    const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Total number of tasks (threads included, as `RLIMIT_NPROC` counts them) owned by the
+    /// current user.
+    pub fn user_task_count() -> u64 {
+        // SAFETY: getuid has no preconditions and can't fail.
+        let uid = unsafe { libc::getuid() }.to_string();
+
+        let Ok(entries) = fs::read_dir("/proc") else {
+            return 0;
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| fs::read_to_string(entry.path().join("status")).ok())
+            .filter(|status| {
+                status.lines().any(|line| {
+                    line.strip_prefix("Uid:")
+                        .and_then(|ids| ids.split_whitespace().next())
+                        == Some(uid.as_str())
+                })
+            })
+            .filter_map(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Threads:"))
+                    .and_then(|n| n.trim().parse::<u64>().ok())
+            })
+            .sum()
+    }
+
+    #[derive(Default, Clone, Copy)]
+    struct Counters {
+        memory_max: u32,
+        oom_kill: u32,
+        throttled: u32,
+        pids_max: u32,
+    }
+
+    struct Sample {
+        memory_mb: u32,
+        processes: u32,
+        cpu_usec: u64,
+        counters: Option<Counters>,
+    }
+
+    fn read_keyed(path: &Path, key: &str) -> Option<u64> {
+        fs::read_to_string(path).ok()?.lines().find_map(|line| {
+            let (name, value) = line.split_once(' ')?;
+            (name == key).then(|| value.trim().parse().ok())?
+        })
+    }
+
+    fn read_number(path: &Path) -> Option<u64> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    fn to_u32(n: u64) -> u32 {
+        u32::try_from(n).unwrap_or(u32::MAX)
+    }
+
+    fn sample_cgroup(cgroup: &Path) -> Option<Sample> {
+        // Gone once the sidecar and everything it started have exited
+        let processes = read_number(&cgroup.join("pids.current")).or_else(|| {
+            let procs = fs::read_to_string(cgroup.join("cgroup.procs")).ok()?;
+            Some(procs.lines().count() as u64)
+        })?;
+
+        Some(Sample {
+            memory_mb: to_u32(read_number(&cgroup.join("memory.current")).unwrap_or(0) >> 20),
+            processes: to_u32(processes),
+            cpu_usec: read_keyed(&cgroup.join("cpu.stat"), "usage_usec").unwrap_or(0),
+            counters: Some(Counters {
+                memory_max: to_u32(read_keyed(&cgroup.join("memory.events"), "max").unwrap_or(0)),
+                oom_kill: to_u32(
+                    read_keyed(&cgroup.join("memory.events"), "oom_kill").unwrap_or(0),
+                ),
+                throttled: to_u32(
+                    read_keyed(&cgroup.join("cpu.stat"), "nr_throttled").unwrap_or(0),
+                ),
+                pids_max: to_u32(read_keyed(&cgroup.join("pids.events"), "max").unwrap_or(0)),
+            }),
+        })
+    }
+
+    fn sample_tree(system: &mut System, pid: u32) -> Option<Sample> {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_memory().with_cpu(),
+        );
+
+        let root = Pid::from_u32(pid);
+        system.process(root)?;
+
+        let in_tree = |mut pid: Pid| loop {
+            if pid == root {
+                return true;
+            }
+            match system.process(pid).and_then(|p| p.parent()) {
+                Some(parent) => pid = parent,
+                None => return false,
+            }
+        };
+
+        let (memory, processes, cpu_usec) = system
+            .processes()
+            .iter()
+            .filter(|(pid, _)| in_tree(**pid))
+            .fold((0, 0, 0), |(memory, count, cpu), (_, p)| {
+                (
+                    memory + p.memory(),
+                    count + 1,
+                    cpu + p.accumulated_cpu_time() * 1000,
+                )
+            });
+
+        Some(Sample {
+            memory_mb: to_u32(memory >> 20),
+            processes,
+            cpu_usec,
+            counters: None,
+        })
+    }
+
+    fn report_hits(app: &AppHandle, previous: Counters, current: Counters) {
+        let hits = [
+            (
+                LimitedResource::Memory,
+                previous.memory_max,
+                current.memory_max,
+            ),
+            (LimitedResource::Memory, previous.oom_kill, current.oom_kill),
+            (LimitedResource::Cpu, previous.throttled, current.throttled),
+            (
+                LimitedResource::Processes,
+                previous.pids_max,
+                current.pids_max,
+            ),
+        ];
+
+        for (resource, previous, count) in hits {
+            if count > previous {
+                tracing::warn!(?resource, count, "Sidecar hit its resource limit");
+                let _ = ResourceLimitHit { resource, count }.emit(app);
+            }
+        }
+    }
+
+    /// Samples the sidecar's usage until it exits, reporting limit hits as they happen.
+    pub fn spawn_monitor(app: AppHandle, child: &SidecarChild, limits: ResourceLimits) {
+        let pid = child.pid();
+        let enforcement = child.enforcement();
+        // Without limits the cgroup may lack the memory and pids controllers
+        let cgroup = child
+            .cgroup()
+            .filter(|_| enforcement == Enforcement::Cgroup)
+            .map(PathBuf::from);
+
+        tokio::spawn(async move {
+            let mut system = System::new();
+            let mut last: Option<(Instant, u64)> = None;
+            let mut counters = Counters::default();
+
+            loop {
+                // Both read /proc or /sys, and a full process refresh isn't quick either
+                let cgroup = cgroup.clone();
+                let sampled = tauri::async_runtime::spawn_blocking(move || {
+                    let sample = match &cgroup {
+                        Some(cgroup) => sample_cgroup(cgroup),
+                        None => sample_tree(&mut system, pid),
+                    };
+                    (system, sample)
+                })
+                .await;
+                let Ok((returned, Some(sample))) = sampled else {
+                    break;
+                };
+                system = returned;
+
+                let now = Instant::now();
+                let cpu_percent = last
+                    .map(|(at, usec)| {
+                        let elapsed = now.duration_since(at).as_micros() as f64;
+                        sample.cpu_usec.saturating_sub(usec) as f64 / elapsed * 100.0
+                    })
+                    .unwrap_or(0.0);
+                last = Some((now, sample.cpu_usec));
+
+                if let Some(current) = sample.counters {
+                    report_hits(&app, counters, current);
+                    counters = current;
+                }
+
+                let usage = ResourceUsage {
+                    enforcement,
+                    limits,
+                    memory_mb: sample.memory_mb,
+                    cpu_percent,
+                    processes: sample.processes,
+                    memory_limit_hits: sample.counters.map(|c| c.memory_max),
+                    oom_kills: sample.counters.map(|c| c.oom_kill),
+                    cpu_throttled: sample.counters.map(|c| c.throttled),
+                    process_limit_hits: sample.counters.map(|c| c.pids_max),
+                };
+                *app.state::<ResourceMonitorState>().usage.lock().unwrap() = Some((pid, usage));
+
+                tokio::time::sleep(SAMPLE_INTERVAL).await;
+            }
+
+            // A restarted sidecar may have reported already
+            let state = app.state::<ResourceMonitorState>();
+            let mut usage = state.usage.lock().unwrap();
+            if usage.as_ref().is_some_and(|(sampled, _)| *sampled == pid) {
+                *usage = None;
+            }
+
+            tracing::debug!(pid, "Sidecar exited, stopped resource monitor");
+        });
+    }
+}
@@ -1,84 +1,193 @@
-use std::time::{Duration, Instant};
+use std::{
+    path::PathBuf,
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
 
-use tauri::AppHandle;
+use tauri::{AppHandle, Manager};
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogResult};
-use tauri_plugin_shell::process::CommandChild;
+use tauri_plugin_shell::process::TerminatedPayload;
 use tauri_plugin_store::StoreExt;
-use tokio::task::JoinHandle;
+use tauri_specta::Event;
+use tokio::{sync::oneshot, task::JoinHandle, time::timeout};
 
+#[cfg(unix)]
+use crate::shell_env;
 use crate::{
-    cli,
-    constants::{DEFAULT_SERVER_URL_KEY, SETTINGS_STORE},
+    ServerReadyData, ServerState,
+    cli::{self, SidecarChild},
+    command_error::{CommandError, CommandErrorKind},
+    constants::{SETTINGS_STORE, SHUTDOWN_GRACE_PERIOD_KEY},
+    init,
+    ports::{self, PortSource},
+    profiles::{self, ServerAuth, ServerProfile, ServerTls},
+    redact,
+    startup_error::StartupError,
+    tls,
+    transport::SidecarAddr,
 };
 
-#[tauri::command]
-#[specta::specta]
-pub fn get_default_server_url(app: AppHandle) -> Result<Option<String>, String> {
-    let store = app
-        .store(SETTINGS_STORE)
-        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+pub const SIDECAR_USERNAME: &str = "opencode";
+
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(30);
+
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+const RESTART_BACKOFF_BASE: Duration = Duration::from_millis(500);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+// A sidecar that stays up this long is considered stable and resets the crash-loop counter.
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+pub const DEFAULT_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(5);
+// App exit blocks on shutdown, so a sidecar ignoring SIGTERM can't hold it up for longer.
+const MAX_SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(60);
+#[cfg(unix)]
+const SHUTDOWN_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerPhase {
+    Starting,
+    Running,
+    Restarting,
+    Failed,
+    Stopping,
+    Stopped,
+}
 
-    let value = store.get(DEFAULT_SERVER_URL_KEY);
-    match value {
-        Some(v) => Ok(v.as_str().map(String::from)),
-        None => Ok(None),
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+pub struct SidecarExit {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl From<TerminatedPayload> for SidecarExit {
+    fn from(payload: TerminatedPayload) -> Self {
+        Self {
+            code: payload.code,
+            signal: payload.signal,
+        }
     }
 }
 
-#[tauri::command]
-#[specta::specta]
-pub async fn set_default_server_url(app: AppHandle, url: Option<String>) -> Result<(), String> {
-    let store = app
-        .store(SETTINGS_STORE)
-        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, tauri_specta::Event, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatusChanged {
+    pub phase: ServerPhase,
+    pub attempt: u32,
+    pub last_exit: Option<SidecarExit>,
+}
 
-    match url {
-        Some(u) => {
-            store.set(DEFAULT_SERVER_URL_KEY, serde_json::Value::String(u));
-        }
-        None => {
-            store.delete(DEFAULT_SERVER_URL_KEY);
-        }
+pub fn emit_status(
+    app: &AppHandle,
+    phase: ServerPhase,
+    attempt: u32,
+    last_exit: Option<SidecarExit>,
+) {
+    tracing::debug!(?phase, attempt, ?last_exit, "Server status changed");
+
+    let _ = ServerStatusChanged {
+        phase,
+        attempt,
+        last_exit,
     }
+    .emit(app);
+}
 
-    store
-        .save()
-        .map_err(|e| format!("Failed to save settings: {}", e))?;
+#[tauri::command]
+#[specta::specta]
+pub fn get_default_server_url(app: AppHandle) -> Result<Option<String>, CommandError> {
+    Ok(profiles::read(&app)?
+        .into_iter()
+        .find(|p| p.is_default)
+        .map(|p| p.url))
+}
 
-    Ok(())
+#[tauri::command]
+#[specta::specta]
+pub async fn set_default_server_url(
+    app: AppHandle,
+    url: Option<String>,
+) -> Result<(), CommandError> {
+    profiles::set_default_url(&app, url)
 }
 
-pub async fn get_saved_server_url(app: &tauri::AppHandle) -> Option<String> {
-    if let Some(url) = get_default_server_url(app.clone()).ok().flatten() {
-        tracing::info!(%url, "Using desktop-specific custom URL");
-        return Some(url);
+pub async fn get_saved_server(app: &tauri::AppHandle) -> Option<ServerProfile> {
+    if let Some(profile) = profiles::get_default(app) {
+        if let Some(auth) = &profile.auth {
+            redact::register(auth.secret());
+        }
+        tracing::info!(url = %profile.url, name = %profile.name, "Using default server profile");
+        return Some(profile);
+    }
+
+    // Reading the config runs the sidecar, which waits for the login shell's environment
+    #[cfg(unix)]
+    {
+        init::set_phase(app, init::InitPhase::ResolvingShellEnv);
+        shell_env::warm().await;
+        init::set_phase(app, init::InitPhase::ResolvingConfig);
     }
 
     if let Some(cli_config) = cli::get_config(app).await
         && let Some(url) = get_server_url_from_config(&cli_config)
     {
         tracing::info!(%url, "Using custom server URL from config");
-        return Some(url);
+        return Some(ServerProfile::from_url("OpenCode config", url));
     }
 
     None
 }
 
+#[derive(Debug)]
+pub enum SpawnError {
+    /// Something else took the port before the sidecar could bind it.
+    PortInUse(u16),
+    /// The sidecar binary doesn't exist.
+    Missing(PathBuf),
+    /// The sidecar process couldn't be started.
+    Spawn(String),
+    /// The sidecar exited before becoming healthy.
+    Exited {
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
+    TimedOut,
+    Failed(String),
+}
+
+impl std::fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PortInUse(port) => write!(f, "Port {port} is already in use"),
+            Self::Missing(path) => write!(f, "Sidecar binary not found at {}", path.display()),
+            Self::Spawn(message) => write!(f, "Failed to spawn sidecar: {message}"),
+            Self::Exited { code, signal } => write!(
+                f,
+                "Sidecar terminated before becoming healthy (code={code:?} signal={signal:?})"
+            ),
+            Self::TimedOut => f.write_str("Health check timed out"),
+            Self::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
 pub fn spawn_local_server(
     app: AppHandle,
-    hostname: String,
-    port: u32,
+    addr: SidecarAddr,
     password: String,
-) -> (CommandChild, HealthCheck) {
-    let (child, exit) = cli::serve(&app, &hostname, port, &password);
+) -> Result<(SidecarChild, HealthCheck), SpawnError> {
+    let (child, mut exit, port_in_use) = cli::serve(&app, &addr, &password)?;
 
     let health_check = HealthCheck(tokio::spawn(async move {
-        let url = format!("http://{hostname}:{port}");
+        let url = addr.url();
         let timestamp = Instant::now();
 
         let ready = async {
+            let mut attempt = 0;
             loop {
                 tokio::time::sleep(Duration::from_millis(100)).await;
+                attempt += 1;
+                init::health_attempt(&app, attempt);
 
                 if check_health(&url, Some(&password)).await {
                     tracing::info!(elapsed = ?timestamp.elapsed(), "Server ready");
@@ -88,32 +197,516 @@ pub fn spawn_local_server(
         };
 
         let terminated = async {
-            match exit.await {
-                Ok(payload) => Err(format!(
-                    "Sidecar terminated before becoming healthy (code={:?} signal={:?})",
-                    payload.code, payload.signal
-                )),
-                Err(_) => Err("Sidecar terminated before becoming healthy".to_string()),
+            let payload = (&mut exit).await;
+
+            // The sidecar's own message is the main signal, but also catch the port having
+            // been taken in the meantime by something that doesn't print one
+            if let Some(port) = addr.port()
+                && (port_in_use.load(Ordering::SeqCst) || !ports::is_free(port))
+            {
+                return Err(SpawnError::PortInUse(port));
             }
+
+            let (code, signal) = payload.map_or((None, None), |p| (p.code, p.signal));
+            Err(SpawnError::Exited { code, signal })
         };
 
-        tokio::select! {
+        let res = tokio::select! {
             res = ready => res,
             res = terminated => res,
-        }
+        };
+
+        // Hand the exit receiver back so the supervisor can keep watching the sidecar
+        res.map(|()| exit)
     }));
 
-    (child, health_check)
+    Ok((child, health_check))
+}
+
+/// Resolves once the sidecar is healthy, yielding the receiver that fires when it later exits.
+pub struct HealthCheck(pub JoinHandle<Result<oneshot::Receiver<TerminatedPayload>, SpawnError>>);
+
+const MAX_PORT_ATTEMPTS: usize = 5;
+
+pub struct LocalServer {
+    pub child: SidecarChild,
+    pub exit: oneshot::Receiver<TerminatedPayload>,
+    pub addr: SidecarAddr,
+}
+
+/// Waits for the first health check, for longer than [`HEALTH_CHECK_TIMEOUT`] while the
+/// sidecar is migrating its database.
+async fn await_startup(
+    app: &AppHandle,
+    health_check: &mut HealthCheck,
+) -> Result<oneshot::Receiver<TerminatedPayload>, SpawnError> {
+    loop {
+        match timeout(HEALTH_CHECK_TIMEOUT, &mut health_check.0).await {
+            Ok(Ok(res)) => return res,
+            Ok(Err(e)) => {
+                return Err(SpawnError::Failed(format!("Health check task failed: {e}")));
+            }
+            Err(_) if init::is_migrating(app) => {
+                tracing::info!("Sidecar is still migrating, extending health check deadline");
+            }
+            Err(_) => return Err(SpawnError::TimedOut),
+        }
+    }
+}
+
+/// Waits for a freshly spawned sidecar to become healthy. If its port turns out to be taken it
+/// is respawned on a fresh one, unless the user pinned the port. A sidecar that can't start on
+/// a Unix socket is respawned on TCP.
+pub async fn await_local_server(
+    app: AppHandle,
+    hostname: String,
+    password: String,
+    ports: PortSource,
+    spawned: (SidecarChild, HealthCheck, SidecarAddr),
+) -> Result<LocalServer, StartupError> {
+    let (mut child, mut health_check, mut addr) = spawned;
+    let pick = |tried: &[u16]| {
+        ports
+            .pick(tried)
+            .map_err(|e| StartupError::port_conflict(ports.pinned(), e))
+    };
+    let mut tried = Vec::from_iter(addr.port());
+
+    loop {
+        let err = match await_startup(&app, &mut health_check).await {
+            Ok(exit) => return Ok(LocalServer { child, exit, addr }),
+            Err(SpawnError::PortInUse(taken)) => {
+                if ports.can_retry() && tried.len() < MAX_PORT_ATTEMPTS {
+                    let _ = child.kill();
+
+                    let port = pick(&tried)?;
+                    tracing::warn!(taken, port, "Sidecar port already in use, retrying");
+                    tried.push(port);
+
+                    addr = SidecarAddr::tcp(&hostname, port);
+                    (child, health_check) =
+                        spawn_local_server(app.clone(), addr.clone(), password.clone())?;
+                    continue;
+                }
+
+                tracing::error!(port = taken, "Sidecar port already in use");
+                StartupError::port_conflict(Some(taken), ports.conflict_message(taken))
+            }
+            Err(e) => StartupError::from(e),
+        };
+
+        let _ = child.kill();
+
+        #[cfg(unix)]
+        if let SidecarAddr::Unix { .. } = addr {
+            tracing::warn!("Sidecar failed to start on its socket, falling back to TCP: {err}");
+
+            let port = pick(&tried)?;
+            tried.push(port);
+
+            addr = SidecarAddr::tcp(&hostname, port);
+            (child, health_check) =
+                spawn_local_server(app.clone(), addr.clone(), password.clone())?;
+            continue;
+        }
+
+        return Err(err);
+    }
+}
+
+/// Watches a healthy sidecar and restarts it on the same address and password when it exits
+/// unexpectedly, so the webview's server URL stays valid across restarts.
+pub fn supervise(
+    app: AppHandle,
+    exit: oneshot::Receiver<TerminatedPayload>,
+    addr: SidecarAddr,
+    password: String,
+) {
+    tokio::spawn(async move {
+        let mut exit = exit;
+        let mut attempt = 0;
+        let mut started_at = Instant::now();
+
+        loop {
+            let mut last_exit = exit.await.ok().map(SidecarExit::from);
+
+            let state = app.state::<ServerState>();
+            if state.is_stopping() {
+                tracing::info!("Sidecar stopped, supervisor exiting");
+                state.stopped.notify_one();
+                emit_status(&app, ServerPhase::Stopped, attempt, last_exit);
+                return;
+            }
+            state.set_child(None);
+
+            let requested = state.take_restart_request();
+            if requested {
+                tracing::info!("Restarting sidecar on request");
+                attempt = 0;
+                last_exit = None;
+            } else {
+                tracing::warn!(?last_exit, uptime = ?started_at.elapsed(), "Sidecar exited unexpectedly");
+
+                if started_at.elapsed() >= STABLE_UPTIME {
+                    attempt = 0;
+                }
+            }
+
+            loop {
+                attempt += 1;
+
+                if attempt > MAX_RESTART_ATTEMPTS {
+                    tracing::error!(
+                        attempts = MAX_RESTART_ATTEMPTS,
+                        "Sidecar is crash looping, giving up"
+                    );
+                    emit_status(&app, ServerPhase::Failed, attempt - 1, last_exit);
+                    return;
+                }
+
+                emit_status(&app, ServerPhase::Restarting, attempt, last_exit.clone());
+
+                let backoff = if requested && attempt == 1 {
+                    Duration::ZERO
+                } else {
+                    restart_backoff(attempt)
+                };
+                tracing::info!(attempt, ?backoff, "Restarting sidecar");
+                tokio::time::sleep(backoff).await;
+
+                if state.is_stopping() {
+                    emit_status(&app, ServerPhase::Stopped, attempt, last_exit);
+                    return;
+                }
+
+                // Picks up profile changes made since the sidecar was last started
+                #[cfg(unix)]
+                shell_env::warm().await;
+
+                let (child, health_check) =
+                    match spawn_local_server(app.clone(), addr.clone(), password.clone()) {
+                        Ok(spawned) => spawned,
+                        Err(e) => {
+                            tracing::warn!(attempt, "Sidecar restart failed: {e}");
+                            last_exit = None;
+                            continue;
+                        }
+                    };
+
+                let err = match timeout(HEALTH_CHECK_TIMEOUT, health_check.0).await {
+                    Ok(Ok(Ok(next_exit))) => {
+                        #[cfg(windows)]
+                        app.state::<crate::job_object::JobObjectState>()
+                            .assign_pid(child.pid());
+
+                        state.set_child(Some(child));
+
+                        // kill_sidecar may have run while we were spawning
+                        if state.is_stopping() {
+                            if let Some(child) = state.take_child() {
+                                let _ = child.kill();
+                            }
+                            emit_status(&app, ServerPhase::Stopped, attempt, None);
+                            return;
+                        }
+
+                        tracing::info!(attempt, "Sidecar restarted");
+                        emit_status(&app, ServerPhase::Running, attempt, None);
+
+                        exit = next_exit;
+                        started_at = Instant::now();
+                        break;
+                    }
+                    Ok(Ok(Err(e))) => e.to_string(),
+                    Ok(Err(e)) => format!("Health check task failed: {e}"),
+                    Err(_) => "Health check timed out".to_string(),
+                };
+
+                tracing::warn!(attempt, "Sidecar restart failed: {err}");
+                let _ = child.kill();
+                last_exit = None;
+            }
+        }
+    });
+}
+
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownStep {
+    /// Asking the server to dispose its instances so pending writes are flushed.
+    Disposing,
+    /// Signalled the sidecar to exit and waiting for it to do so.
+    Waiting,
+    /// The grace period ran out and the sidecar is being killed.
+    ForceKilling,
+    Exited,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize, specta::Type, tauri_specta::Event, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownProgress {
+    pub step: ShutdownStep,
+    pub elapsed_ms: u32,
+    pub grace_period_ms: u32,
+}
+
+fn emit_shutdown_progress(app: &AppHandle, step: ShutdownStep, started: Instant, grace: Duration) {
+    let _ = ShutdownProgress {
+        step,
+        elapsed_ms: u32::try_from(started.elapsed().as_millis()).unwrap_or(u32::MAX),
+        grace_period_ms: u32::try_from(grace.as_millis()).unwrap_or(u32::MAX),
+    }
+    .emit(app);
+}
+
+fn shutdown_grace_period(app: &AppHandle) -> Duration {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SHUTDOWN_GRACE_PERIOD_KEY))
+        .and_then(|v| v.as_u64())
+        .map(Duration::from_millis)
+        .map(|grace| grace.min(MAX_SHUTDOWN_GRACE_PERIOD))
+        .unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_shutdown_grace_period(app: AppHandle) -> u32 {
+    u32::try_from(shutdown_grace_period(&app).as_millis()).unwrap_or(u32::MAX)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_shutdown_grace_period(app: AppHandle, grace_period_ms: u32) -> Result<(), CommandError> {
+    if Duration::from_millis(grace_period_ms.into()) > MAX_SHUTDOWN_GRACE_PERIOD {
+        return Err(CommandError::invalid_input(
+            "gracePeriodMs",
+            format!(
+                "Shutdown grace period can be at most {}ms",
+                MAX_SHUTDOWN_GRACE_PERIOD.as_millis()
+            ),
+        ));
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(CommandError::settings_open)?;
+
+    store.set(
+        SHUTDOWN_GRACE_PERIOD_KEY,
+        serde_json::json!(grace_period_ms),
+    );
+    store.save().map_err(CommandError::settings_save)?;
+
+    Ok(())
 }
 
-pub struct HealthCheck(pub JoinHandle<Result<(), String>>);
+/// Asks the server to release its instances, which flushes storage and stops tool calls.
+async fn dispose(endpoint: &ServerReadyData) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .no_proxy()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut req = client.post(format!("{}/global/dispose", endpoint.url));
+    if let Some(ServerAuth::Basic { username, password }) = &endpoint.auth {
+        req = req.basic_auth(username, Some(password));
+    }
+
+    let res = req.send().await.map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("Server responded with {}", res.status()));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn terminate(child: &SidecarChild) -> Result<(), String> {
+    let pid = child.pid() as libc::pid_t;
+    // SAFETY: kill has no memory safety requirements; the pid belongs to our own child.
+    if unsafe { libc::kill(pid, libc::SIGTERM) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error().to_string())
+    }
+}
+
+/// Stops the sidecar gracefully: dispose over HTTP, SIGTERM, wait out the grace period and
+/// only then kill it. Safe to call more than once; later calls return immediately.
+pub async fn shutdown(app: &AppHandle) {
+    let Some(state) = app.try_state::<ServerState>() else {
+        tracing::info!("Server not running");
+        return;
+    };
+
+    state.set_stopping();
+
+    let Some(child) = state.take_child() else {
+        tracing::info!("Server state missing");
+        return;
+    };
+
+    let grace = shutdown_grace_period(app);
+    let started = Instant::now();
+    tracing::info!(?grace, "Shutting down sidecar");
+    emit_status(app, ServerPhase::Stopping, 0, None);
+
+    if let Some(endpoint) = state.endpoint() {
+        emit_shutdown_progress(app, ShutdownStep::Disposing, started, grace);
+
+        match timeout(grace, dispose(&endpoint)).await {
+            Ok(Ok(())) => tracing::info!(elapsed = ?started.elapsed(), "Sidecar disposed"),
+            Ok(Err(e)) => tracing::warn!("Failed to dispose sidecar: {e}"),
+            Err(_) => tracing::warn!("Timed out disposing sidecar"),
+        }
+    }
+
+    // Windows has no SIGTERM, so once disposed there's nothing left to wait for
+    #[cfg(unix)]
+    let exited = match terminate(&child) {
+        Ok(()) => {
+            let wait = async {
+                let mut progress = tokio::time::interval(SHUTDOWN_PROGRESS_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = state.stopped.notified() => break,
+                        _ = progress.tick() => {
+                            emit_shutdown_progress(app, ShutdownStep::Waiting, started, grace);
+                        }
+                    }
+                }
+            };
+
+            timeout(grace.saturating_sub(started.elapsed()), wait)
+                .await
+                .is_ok()
+        }
+        Err(e) => {
+            tracing::warn!("Failed to send SIGTERM to sidecar: {e}");
+            false
+        }
+    };
+    #[cfg(not(unix))]
+    let exited = false;
+
+    if exited {
+        tracing::info!(elapsed = ?started.elapsed(), "Sidecar exited gracefully");
+    } else {
+        tracing::warn!(elapsed = ?started.elapsed(), "Sidecar still running, killing it");
+        emit_shutdown_progress(app, ShutdownStep::ForceKilling, started, grace);
+        let _ = child.kill();
+    }
+
+    emit_shutdown_progress(app, ShutdownStep::Exited, started, grace);
+}
+
+/// Restarts the local sidecar on the same address, so that changed launch options take effect
+/// without reloading the app.
+#[tauri::command]
+#[specta::specta]
+pub async fn restart_sidecar(app: AppHandle) -> Result<(), CommandError> {
+    let not_running = || {
+        CommandError::new(
+            CommandErrorKind::ServerNotRunning,
+            "The app isn't running a local server",
+        )
+    };
+    let state = app.try_state::<ServerState>().ok_or_else(not_running)?;
+
+    if state.is_stopping() {
+        return Err(CommandError::new(
+            CommandErrorKind::ServerStopping,
+            "The server is shutting down",
+        ));
+    }
+    if state.child_pid().is_none() {
+        return Err(not_running());
+    }
+
+    tracing::info!("Restarting sidecar");
+    state.request_restart();
+
+    // Let the server flush pending writes before it is killed
+    if let Some(endpoint) = state.endpoint() {
+        match timeout(shutdown_grace_period(&app), dispose(&endpoint)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("Failed to dispose sidecar before restart: {e}"),
+            Err(_) => tracing::warn!("Timed out disposing sidecar before restart"),
+        }
+    }
+
+    if let Some(child) = state.take_child() {
+        child
+            .kill()
+            .map_err(|e| CommandError::internal(format!("Failed to stop the server: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+fn restart_backoff(attempt: u32) -> Duration {
+    RESTART_BACKOFF_BASE
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(RESTART_BACKOFF_MAX)
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    /// The server answered 401 or 403, so it's up but needs (different) credentials.
+    Unauthorized {
+        code: u16,
+    },
+    Unhealthy {
+        code: u16,
+    },
+    Unreachable {
+        message: String,
+    },
+    /// The TLS handshake failed because the server's certificate isn't trusted (or doesn't
+    /// match the pinned fingerprint).
+    UntrustedCertificate {
+        message: String,
+    },
+}
+
+/// Credentials the desktop sidecar is started with.
+pub fn sidecar_auth(password: &str) -> ServerAuth {
+    ServerAuth::Basic {
+        username: SIDECAR_USERNAME.to_string(),
+        password: password.to_string(),
+    }
+}
 
 pub async fn check_health(url: &str, password: Option<&str>) -> bool {
-    let Ok(url) = reqwest::Url::parse(url) else {
-        return false;
+    let auth = password.map(sidecar_auth);
+
+    matches!(
+        probe_health(url, auth.as_ref(), None).await,
+        HealthStatus::Healthy
+    )
+}
+
+pub async fn probe_health(
+    url: &str,
+    auth: Option<&ServerAuth>,
+    tls: Option<&ServerTls>,
+) -> HealthStatus {
+    let url = match reqwest::Url::parse(url) {
+        Ok(url) => url,
+        Err(e) => {
+            return HealthStatus::Unreachable {
+                message: format!("Invalid URL: {e}"),
+            };
+        }
     };
 
-    let mut builder = reqwest::Client::builder().timeout(Duration::from_secs(3));
+    let mut builder = match tls::client_builder(tls) {
+        Ok(builder) => builder.timeout(Duration::from_secs(3)),
+        Err(message) => return HealthStatus::Unreachable { message },
+    };
 
     if url_is_localhost(&url) {
         // Some environments set proxy variables (HTTP_PROXY/HTTPS_PROXY/ALL_PROXY) without
@@ -122,23 +715,54 @@ pub async fn check_health(url: &str, password: Option<&str>) -> bool {
         builder = builder.no_proxy();
     };
 
-    let Ok(client) = builder.build() else {
-        return false;
+    let client = match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            return HealthStatus::Unreachable {
+                message: e.to_string(),
+            };
+        }
     };
     let Ok(health_url) = url.join("/global/health") else {
-        return false;
+        return HealthStatus::Unreachable {
+            message: "Invalid health check URL".to_string(),
+        };
     };
 
     let mut req = client.get(health_url);
 
-    if let Some(password) = password {
-        req = req.basic_auth("opencode", Some(password));
+    match auth {
+        Some(ServerAuth::Basic { username, password }) => {
+            req = req.basic_auth(username, Some(password));
+        }
+        Some(ServerAuth::Bearer { token }) => {
+            req = req.bearer_auth(token);
+        }
+        None => {}
     }
 
-    req.send()
-        .await
-        .map(|r| r.status().is_success())
-        .unwrap_or(false)
+    match req.send().await {
+        Ok(res) if res.status().is_success() => HealthStatus::Healthy,
+        Ok(res)
+            if matches!(
+                res.status(),
+                reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN
+            ) =>
+        {
+            HealthStatus::Unauthorized {
+                code: res.status().as_u16(),
+            }
+        }
+        Ok(res) => HealthStatus::Unhealthy {
+            code: res.status().as_u16(),
+        },
+        Err(e) if tls::is_certificate_error(&e) => HealthStatus::UntrustedCertificate {
+            message: e.to_string(),
+        },
+        Err(e) => HealthStatus::Unreachable {
+            message: e.to_string(),
+        },
+    }
 }
 
 fn url_is_localhost(url: &reqwest::Url) -> bool {
@@ -183,20 +807,92 @@ fn get_server_url_from_config(config: &cli::Config) -> Option<String> {
     Some(format!("http://{}:{}", hostname, port))
 }
 
-pub async fn check_health_or_ask_retry(app: &AppHandle, url: &str) -> bool {
+pub async fn check_health_or_ask_retry(app: &AppHandle, profile: &mut ServerProfile) -> bool {
+    let url = profile.url.clone();
     tracing::debug!(%url, "Checking health");
     loop {
-        if check_health(url, None).await {
-            return true;
-        }
+        let message = match probe_health(&url, profile.auth.as_ref(), profile.tls.as_ref()).await {
+            HealthStatus::Healthy => return true,
+            HealthStatus::Unauthorized { code } => {
+                tracing::warn!(%url, code, has_credentials = profile.auth.is_some(), "Server rejected credentials");
+
+                if profile.auth.is_some() {
+                    format!(
+                        "The configured server rejected the saved credentials ({}):\n{}\n\nUpdate the credentials for \"{}\" in settings, or start a local server instead.",
+                        code, url, profile.name
+                    )
+                } else {
+                    format!(
+                        "The configured server requires credentials ({}):\n{}\n\nAdd a username and password or token for \"{}\" in settings, or start a local server instead.",
+                        code, url, profile.name
+                    )
+                }
+            }
+            HealthStatus::Unhealthy { code } => {
+                tracing::warn!(%url, code, "Server reported unhealthy");
+                format!(
+                    "The configured server responded with an error ({}):\n{}\n\nWould you like to retry or start a local server instead?",
+                    code, url
+                )
+            }
+            HealthStatus::Unreachable { message } => {
+                tracing::warn!(%url, "Server unreachable: {message}");
+                format!(
+                    "Could not connect to configured server:\n{}\n\nWould you like to retry or start a local server instead?",
+                    url
+                )
+            }
+            HealthStatus::UntrustedCertificate { message } => {
+                tracing::warn!(%url, "Server certificate not trusted: {message}");
+
+                let pinned = profile
+                    .tls
+                    .as_ref()
+                    .and_then(|tls| tls.pinned_fingerprint.as_deref())
+                    .is_some_and(|v| !v.is_empty());
+
+                if !pinned && let Ok(fingerprint) = tls::fetch_fingerprint(&url).await {
+                    if ask_trust_certificate(app, &url, &fingerprint) {
+                        tracing::info!(%url, %fingerprint, "Pinning server certificate");
+
+                        if !profile.id.is_empty()
+                            && let Err(e) =
+                                profiles::pin_certificate(app, &profile.id, &fingerprint)
+                        {
+                            tracing::warn!("Failed to save pinned certificate: {e}");
+                        }
+                        profile.tls.get_or_insert_default().pinned_fingerprint = Some(fingerprint);
+
+                        continue;
+                    }
+                    break;
+                }
+
+                if pinned {
+                    format!(
+                        "The configured server's certificate no longer matches the pinned fingerprint:\n{}\n\nIf the certificate was renewed, update the fingerprint for \"{}\" in settings, or start a local server instead.",
+                        url, profile.name
+                    )
+                } else {
+                    format!(
+                        "The configured server's certificate is not trusted:\n{}\n\nWould you like to retry or start a local server instead?",
+                        url
+                    )
+                }
+            }
+        };
 
         const RETRY: &str = "Retry";
 
-        let res = app.dialog()
-    		  .message(format!("Could not connect to configured server:\n{}\n\nWould you like to retry or start a local server instead?", url))
-    		  .title("Connection Failed")
-    		  .buttons(MessageDialogButtons::OkCancelCustom(RETRY.to_string(), "Start Local".to_string()))
-    		  .blocking_show_with_result();
+        let res = app
+            .dialog()
+            .message(message)
+            .title("Connection Failed")
+            .buttons(MessageDialogButtons::OkCancelCustom(
+                RETRY.to_string(),
+                "Start Local".to_string(),
+            ))
+            .blocking_show_with_result();
 
         match res {
             MessageDialogResult::Custom(name) if name == RETRY => {
@@ -210,3 +906,23 @@ pub async fn check_health_or_ask_retry(app: &AppHandle, url: &str) -> bool {
 
     false
 }
+
+/// Trust-on-first-use prompt for a server presenting a certificate we can't verify.
+fn ask_trust_certificate(app: &AppHandle, url: &str, fingerprint: &str) -> bool {
+    const TRUST: &str = "Trust";
+
+    let res = app
+        .dialog()
+        .message(format!(
+            "The server presented a certificate that isn't trusted:\n{}\n\nSHA-256: {}\n\nOnly trust it if this fingerprint matches your server's certificate.",
+            url, fingerprint
+        ))
+        .title("Untrusted Certificate")
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            TRUST.to_string(),
+            "Start Local".to_string(),
+        ))
+        .blocking_show_with_result();
+
+    matches!(res, MessageDialogResult::Custom(name) if name == TRUST)
+}
@@ -0,0 +1,354 @@
+//! The user's login-shell environment, used to launch the sidecar.
+//!
+//! Apps started from a desktop launcher don't see variables set in shell profiles, like `PATH`
+//! additions from version managers or API keys, which the sidecar and the tools it runs rely
+//! on. Rather than starting every sidecar through `$SHELL -il -c`, the shell is run once and
+//! asked to launch this app's own binary in dump mode, which prints its environment as JSON
+//! between two markers so anything the profile prints is ignored. The result is cached until
+//! one of the shell's startup files changes or it's refreshed from the settings. Capturing
+//! blocks while the shell runs, so async code warms the cache first and launches with what's
+//! cached.
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ShellEnvStatus {
+    Captured,
+    Failed,
+    /// Windows, where apps already get the user's environment.
+    Unsupported,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellEnvVar {
+    pub name: String,
+    /// Whether the app's own environment had a different value, rather than none at all.
+    pub overrides: bool,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellEnvDiagnostics {
+    pub shell: Option<String>,
+    pub status: ShellEnvStatus,
+    pub error: Option<String>,
+    pub duration_ms: Option<u32>,
+    /// Variables the shell added or changed. Values are left out as they may hold secrets.
+    pub from_shell: Vec<ShellEnvVar>,
+}
+
+#[cfg(not(unix))]
+fn diagnostics() -> ShellEnvDiagnostics {
+    ShellEnvDiagnostics {
+        shell: None,
+        status: ShellEnvStatus::Unsupported,
+        error: None,
+        duration_ms: None,
+        from_shell: vec![],
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn get_shell_env_diagnostics() -> ShellEnvDiagnostics {
+    tokio::task::spawn_blocking(diagnostics)
+        .await
+        .expect("Shell environment task panicked")
+}
+
+/// Captures the shell environment again, for example after editing a profile in a way that
+/// doesn't change its modification time.
+#[tauri::command]
+#[specta::specta]
+pub async fn refresh_shell_env() -> ShellEnvDiagnostics {
+    #[cfg(unix)]
+    invalidate();
+
+    get_shell_env_diagnostics().await
+}
+
+#[cfg(unix)]
+pub use unix::*;
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        collections::BTreeMap,
+        env,
+        io::Read,
+        os::unix::process::CommandExt,
+        path::{Path, PathBuf},
+        process::{Command, Stdio},
+        sync::{Mutex, PoisonError, mpsc},
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use super::*;
+
+    const DUMP_ENV_VAR: &str = "OPENCODE_DESKTOP_DUMP_ENV";
+    const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+    /// Describe the shell that did the capture rather than the user's environment.
+    const IGNORED: &[&str] = &["_", "OLDPWD", "PWD", "SHLVL", DUMP_ENV_VAR];
+
+    #[derive(Clone)]
+    struct Capture {
+        shell: String,
+        fingerprint: Vec<(PathBuf, Option<SystemTime>)>,
+        result: Result<BTreeMap<String, String>, String>,
+        duration: Duration,
+    }
+
+    static CACHE: Mutex<Option<Capture>> = Mutex::new(None);
+    /// Held while the shell runs, so it runs once at a time while [`CACHE`] stays readable.
+    static CAPTURING: Mutex<()> = Mutex::new(());
+
+    pub fn user_shell() -> String {
+        env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    }
+
+    fn shell_name(shell: &str) -> &str {
+        Path::new(shell)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(shell)
+    }
+
+    /// Files whose changes can affect what the shell exports.
+    fn startup_files(shell: &str) -> Vec<PathBuf> {
+        let Some(home) = dirs::home_dir() else {
+            return vec![];
+        };
+        let config = env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+
+        match shell_name(shell) {
+            "bash" => [".bash_profile", ".bash_login", ".profile", ".bashrc"]
+                .map(|file| home.join(file))
+                .to_vec(),
+            "zsh" => {
+                let dir = env::var_os("ZDOTDIR").map(PathBuf::from).unwrap_or(home);
+                [".zshenv", ".zprofile", ".zshrc", ".zlogin"]
+                    .map(|file| dir.join(file))
+                    .to_vec()
+            }
+            "fish" => vec![config.join("fish/config.fish"), config.join("fish/conf.d")],
+            "nu" => ["env.nu", "config.nu", "login.nu"]
+                .map(|file| config.join("nushell").join(file))
+                .to_vec(),
+            _ => vec![home.join(".profile")],
+        }
+    }
+
+    fn fingerprint(shell: &str) -> Vec<(PathBuf, Option<SystemTime>)> {
+        startup_files(shell)
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+
+    fn quote(arg: &str, nu: bool) -> String {
+        if nu {
+            format!("r#'{arg}'#")
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    }
+
+    /// Whether this process was started by [`capture`] to print its environment.
+    pub fn dump_requested() -> bool {
+        env::var_os(DUMP_ENV_VAR).is_some()
+    }
+
+    /// Prints the environment between the markers [`capture`] looks for.
+    pub fn dump() {
+        let marker = env::var(DUMP_ENV_VAR).unwrap_or_default();
+        let vars = env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+            .collect::<BTreeMap<_, _>>();
+
+        print!(
+            "{marker}{}{marker}",
+            serde_json::to_string(&vars).unwrap_or_default()
+        );
+    }
+
+    fn capture(shell: &str) -> Result<BTreeMap<String, String>, String> {
+        let exe = env::current_exe().map_err(|e| format!("Failed to find app binary: {}", e))?;
+        let exe = exe.to_string_lossy();
+        let marker = uuid::Uuid::new_v4().simple().to_string();
+
+        let script = match shell_name(shell) {
+            "nu" => format!("^{}", quote(&exe, true)),
+            _ => quote(&exe, false),
+        };
+
+        let mut command = Command::new(shell);
+        command
+            .args(["-i", "-l", "-c", &script])
+            .env(DUMP_ENV_VAR, &marker)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+
+        // SAFETY: setsid is async-signal-safe. A new session keeps the interactive shell from
+        // grabbing the terminal the app was started from, and lets us kill whatever it leaves.
+        unsafe {
+            command.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", shell, e))?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+
+        // Profiles may start background jobs that keep stdout open, so stop at the second
+        // marker rather than waiting for EOF
+        let (tx, rx) = mpsc::channel();
+        let end_marker = marker.clone();
+        std::thread::spawn(move || {
+            let mut output = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                match stdout.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => output.extend_from_slice(&buf[..n]),
+                }
+                if String::from_utf8_lossy(&output)
+                    .matches(&end_marker)
+                    .count()
+                    >= 2
+                {
+                    break;
+                }
+            }
+            let _ = tx.send(output);
+        });
+
+        let output = rx.recv_timeout(CAPTURE_TIMEOUT);
+
+        // SAFETY: kill has no memory safety requirements; the shell leads its own session.
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) };
+        let _ = child.wait();
+
+        let output = output.map_err(|_| {
+            format!(
+                "{} didn't finish starting within {}s",
+                shell,
+                CAPTURE_TIMEOUT.as_secs()
+            )
+        })?;
+        let output = String::from_utf8_lossy(&output);
+
+        let json = output
+            .split(&marker)
+            .nth(1)
+            .filter(|_| output.matches(&marker).count() >= 2)
+            .ok_or_else(|| format!("{} exited without reporting its environment", shell))?;
+
+        let mut vars: BTreeMap<String, String> = serde_json::from_str(json)
+            .map_err(|e| format!("Failed to parse shell environment: {}", e))?;
+        vars.retain(|name, _| !IGNORED.contains(&name.as_str()));
+
+        Ok(vars)
+    }
+
+    fn ensure_captured() -> Capture {
+        let _capturing = CAPTURING.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let shell = user_shell();
+        let fingerprint = fingerprint(&shell);
+
+        let cached = CACHE
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|c| c.shell == shell && c.fingerprint == fingerprint);
+        if let Some(cached) = cached {
+            return cached;
+        }
+
+        let started = Instant::now();
+        let result = capture(&shell);
+        let duration = started.elapsed();
+
+        match &result {
+            Ok(vars) => {
+                tracing::info!(%shell, ?duration, count = vars.len(), "Captured shell environment")
+            }
+            Err(e) => {
+                tracing::warn!(%shell, ?duration, "Failed to capture shell environment: {e}")
+            }
+        }
+
+        let capture = Capture {
+            shell,
+            fingerprint,
+            result,
+            duration,
+        };
+        *CACHE.lock().unwrap() = Some(capture.clone());
+
+        capture
+    }
+
+    /// The login-shell environment, captured again if it's missing or stale. Blocks while the
+    /// shell runs, so only call it from a blocking task.
+    pub fn get() -> Option<BTreeMap<String, String>> {
+        ensure_captured().result.ok()
+    }
+
+    /// The environment as last captured, without checking whether it's stale. Never runs the
+    /// shell, so it's safe to call from async code after [`warm`].
+    pub fn cached() -> Option<BTreeMap<String, String>> {
+        CACHE
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|capture| capture.result.clone().ok())
+    }
+
+    /// Captures the environment on a blocking thread if needed, so async callers can use
+    /// [`cached`] after.
+    pub async fn warm() {
+        let _ = tokio::task::spawn_blocking(get).await;
+    }
+
+    pub fn invalidate() {
+        *CACHE.lock().unwrap() = None;
+    }
+
+    pub(super) fn diagnostics() -> ShellEnvDiagnostics {
+        let capture = ensure_captured();
+
+        let (status, error, from_shell) = match &capture.result {
+            Ok(vars) => {
+                let from_shell = vars
+                    .iter()
+                    .filter(|(name, value)| env::var(name).ok().as_ref() != Some(*value))
+                    .map(|(name, _)| ShellEnvVar {
+                        name: name.clone(),
+                        overrides: env::var_os(name).is_some(),
+                    })
+                    .collect();
+                (ShellEnvStatus::Captured, None, from_shell)
+            }
+            Err(e) => (ShellEnvStatus::Failed, Some(e.clone()), vec![]),
+        };
+
+        ShellEnvDiagnostics {
+            shell: Some(capture.shell.clone()),
+            status,
+            error,
+            duration_ms: Some(u32::try_from(capture.duration.as_millis()).unwrap_or(u32::MAX)),
+            from_shell,
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Output of the sidecar.
+//!
+//! Each line is redacted, parsed for the level, timestamp and service the sidecar's logger
+//! prints, then forwarded to tracing at the matching level, appended to a log file of its own and
+//! kept in a bounded buffer that startup errors and the frontend read from.
+
+use std::{
+    collections::VecDeque,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        Mutex, TryLockError,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use crate::{
+    launch_options::SidecarLogLevel,
+    logging::{LogRetention, RotatingFile},
+    redact,
+};
+
+/// Lines kept in memory, across restarts of the sidecar.
+const RECENT_LINES: usize = 1000;
+
+static RECENT: Mutex<VecDeque<SidecarLogLine>> = Mutex::new(VecDeque::new());
+
+static FILE: Mutex<Option<RotatingFile>> = Mutex::new(None);
+
+// Pid of the most recently spawned sidecar
+static CURRENT_PID: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarLogLine {
+    pub pid: u32,
+    pub stream: SidecarStream,
+    /// Set for lines written by the sidecar's logger, unset for other output such as stack
+    /// traces.
+    pub level: Option<SidecarLogLevel>,
+    /// As printed by the sidecar, in UTC without fractional seconds.
+    pub timestamp: Option<String>,
+    pub service: Option<String>,
+    /// The line without its level and timing.
+    pub message: String,
+    /// The line as printed.
+    pub line: String,
+}
+
+/// Opens the sidecar log file of this run in `log_dir`, next to the app's own log file.
+pub fn init(log_dir: &Path, retention: LogRetention) {
+    let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
+
+    match RotatingFile::create(log_dir, format!("opencode-sidecar_{timestamp}"), retention) {
+        Ok(file) => *FILE.lock().unwrap() = Some(file),
+        Err(e) => tracing::warn!("Failed to create sidecar log file: {e}"),
+    }
+}
+
+/// The sidecar log file currently written to.
+pub fn path() -> Option<PathBuf> {
+    let file = FILE.lock().unwrap();
+    let path = file.as_ref()?.path();
+    let path = path.lock().unwrap().clone();
+    Some(path)
+}
+
+/// Marks the start of a new sidecar, whose output `recent_stderr` is limited to.
+pub fn started(pid: u32) {
+    CURRENT_PID.store(pid, Ordering::SeqCst);
+    write_file(&format!("--- Sidecar started (pid {pid}) ---"));
+}
+
+/// Records a line of sidecar output.
+pub fn record(pid: u32, stream: SidecarStream, line: &str) {
+    let line = redact::redact(line.trim_end());
+    let line = line.as_str();
+    let parsed = parse(line);
+    let entry = SidecarLogLine {
+        pid,
+        stream,
+        level: parsed.as_ref().map(|parsed| parsed.level),
+        timestamp: parsed.as_ref().map(|parsed| parsed.timestamp.to_string()),
+        service: parsed
+            .as_ref()
+            .and_then(|parsed| parsed.service.map(String::from)),
+        message: parsed
+            .as_ref()
+            .map_or(line, |parsed| parsed.message)
+            .to_string(),
+        line: line.to_string(),
+    };
+
+    let service = entry.service.as_deref().unwrap_or_default();
+    match entry.level {
+        Some(SidecarLogLevel::Error) => {
+            tracing::error!(target: "sidecar", pid, service, "{}", entry.message)
+        }
+        Some(SidecarLogLevel::Warn) => {
+            tracing::warn!(target: "sidecar", pid, service, "{}", entry.message)
+        }
+        Some(SidecarLogLevel::Debug) => {
+            tracing::debug!(target: "sidecar", pid, service, "{}", entry.message)
+        }
+        Some(SidecarLogLevel::Info) | None => {
+            tracing::info!(target: "sidecar", pid, service, "{}", entry.message)
+        }
+    }
+
+    write_file(&entry.line);
+
+    let mut recent = RECENT.lock().unwrap();
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(entry);
+}
+
+fn write_file(line: &str) {
+    if let Some(file) = FILE.lock().unwrap().as_mut()
+        && let Err(e) = file.write_all(format!("{line}\n").as_bytes())
+    {
+        tracing::warn!("Failed to write sidecar log file: {e}");
+    }
+}
+
+/// The most recent lines of sidecar output, oldest first.
+pub fn recent(limit: usize) -> Vec<SidecarLogLine> {
+    let recent = RECENT.lock().unwrap();
+    let start = recent.len().saturating_sub(limit);
+    recent.range(start..).cloned().collect()
+}
+
+/// Like [`recent`], but returns nothing rather than waiting for the buffer, for the panic hook.
+pub fn try_recent(limit: usize) -> Vec<SidecarLogLine> {
+    let recent = match RECENT.try_lock() {
+        Ok(recent) => recent,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return vec![],
+    };
+    let start = recent.len().saturating_sub(limit);
+    recent.range(start..).cloned().collect()
+}
+
+/// What the most recently spawned sidecar wrote to stderr, oldest first.
+pub fn recent_stderr() -> Vec<String> {
+    let pid = CURRENT_PID.load(Ordering::SeqCst);
+    RECENT
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.pid == pid && entry.stream == SidecarStream::Stderr)
+        .map(|entry| entry.line.clone())
+        .collect()
+}
+
+/// A line printed by the sidecar's logger.
+pub struct ParsedLine<'a> {
+    pub level: SidecarLogLevel,
+    /// In UTC without fractional seconds.
+    pub timestamp: &'a str,
+    pub service: Option<&'a str>,
+    /// The line without its level and timing.
+    pub message: &'a str,
+}
+
+/// Parses a line of the sidecar's logger, like
+/// `WARN  2025-01-01T00:00:00 +3ms service=storage index=2 total=5 running migration`.
+/// Returns `None` for other output.
+pub fn parse(line: &str) -> Option<ParsedLine<'_>> {
+    let (level, rest) = line.split_once(' ')?;
+    let level = SidecarLogLevel::from_arg(level)?;
+
+    let rest = rest.trim_start();
+    let (timestamp, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%dT%H:%M:%S").ok()?;
+
+    // Time since the previous line, e.g. `+3ms`
+    let message = rest
+        .strip_prefix('+')
+        .and_then(|rest| rest.split_once("ms "))
+        .map_or(rest, |(_, rest)| rest);
+
+    Some(ParsedLine {
+        level,
+        timestamp,
+        service: message
+            .split_whitespace()
+            .find_map(|token| token.strip_prefix("service=")),
+        message,
+    })
+}
+
+/// Recent sidecar output, oldest first. Returns up to `limit` lines, or all that are kept.
+#[tauri::command]
+#[specta::specta]
+pub fn get_sidecar_output(limit: Option<u32>) -> Vec<SidecarLogLine> {
+    recent(limit.map_or(RECENT_LINES, |limit| limit as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_logger_lines() {
+        // Level, timestamp, service and message
+        let cases = [
+            (
+                "INFO  2025-06-01T12:00:00 +3ms service=storage index=2 total=5 running migration",
+                SidecarLogLevel::Info,
+                "2025-06-01T12:00:00",
+                Some("storage"),
+                "service=storage index=2 total=5 running migration",
+            ),
+            (
+                "WARN  2025-06-01T12:00:00 +0ms service=default stale lock",
+                SidecarLogLevel::Warn,
+                "2025-06-01T12:00:00",
+                Some("default"),
+                "service=default stale lock",
+            ),
+            (
+                "ERROR 2025-06-01T12:00:01 +1204ms service=server error=Not found Caused by: ENOENT failed",
+                SidecarLogLevel::Error,
+                "2025-06-01T12:00:01",
+                Some("server"),
+                "service=server error=Not found Caused by: ENOENT failed",
+            ),
+            (
+                "DEBUG 2025-06-01T12:00:00 +5ms bootstrapping",
+                SidecarLogLevel::Debug,
+                "2025-06-01T12:00:00",
+                None,
+                "bootstrapping",
+            ),
+        ];
+
+        for (line, level, timestamp, service, message) in cases {
+            let parsed = parse(line).unwrap_or_else(|| panic!("{line:?} didn't parse"));
+            assert_eq!(parsed.level, level, "{line:?}");
+            assert_eq!(parsed.timestamp, timestamp, "{line:?}");
+            assert_eq!(parsed.service, service, "{line:?}");
+            assert_eq!(parsed.message, message, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn ignores_other_output() {
+        for line in [
+            "opencode server listening on http://127.0.0.1:4096",
+            "Warning: OPENCODE_SERVER_PASSWORD is not set; server is unsecured.",
+            "opencode-migration index=0 total=5",
+            "INFO  not-a-time +3ms service=storage message",
+            "    at Object.run (src/index.ts:42:7)",
+            "",
+        ] {
+            assert!(parse(line).is_none(), "{line:?}");
+        }
+    }
+}
@@ -0,0 +1,189 @@
+//! Record of the sidecars this app has spawned, used to clean up after crashes.
+//!
+//! Every sidecar is written to `sidecars.json` in the app's local data dir together with the
+//! desktop process that owns it. On the next launch, entries whose owner is gone are orphans:
+//! if the recorded process is still running and is still the same sidecar (same start time and
+//! binary, so a reused pid is never touched) it is killed. Sidecars from other installs and
+//! servers started from the CLI are never in this file, so they are left alone.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tauri::{AppHandle, Manager};
+
+const REGISTRY_FILE: &str = "sidecars.json";
+
+// Serialises read-modify-write cycles of the registry file within this process
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct SidecarRecord {
+    pid: u32,
+    /// Unset for sidecars listening on a Unix socket.
+    port: Option<u16>,
+    /// Process start time in seconds since the epoch, as reported by the OS.
+    started_at: u64,
+    binary: PathBuf,
+    owner_pid: u32,
+    owner_started_at: u64,
+}
+
+fn registry_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_local_data_dir()
+        .inspect_err(|e| tracing::warn!("Failed to resolve app data dir: {e}"))
+        .ok()
+        .map(|dir| dir.join(REGISTRY_FILE))
+}
+
+fn read(path: &Path) -> Vec<SidecarRecord> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return vec![];
+    };
+
+    serde_json::from_str(&contents)
+        .inspect_err(|e| tracing::warn!("Ignoring unreadable sidecar registry: {e}"))
+        .unwrap_or_default()
+}
+
+fn write(path: &Path, records: &[SidecarRecord]) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            let json = serde_json::to_vec_pretty(records).map_err(std::io::Error::other)?;
+            std::fs::write(path, json)
+        });
+
+    if let Err(e) = result {
+        tracing::warn!(path = %path.display(), "Failed to write sidecar registry: {e}");
+    }
+}
+
+fn load_process(system: &mut System, pid: u32) -> Option<&Process> {
+    let pid = Pid::from_u32(pid);
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing()
+            .with_exe(UpdateKind::OnlyIfNotSet)
+            .with_cmd(UpdateKind::OnlyIfNotSet),
+    );
+    system.process(pid)
+}
+
+/// Whether `pid` is still the process that was recorded, rather than a reuse of its pid.
+fn is_same_process(system: &mut System, pid: u32, started_at: u64) -> bool {
+    load_process(system, pid).is_some_and(|p| p.start_time() == started_at)
+}
+
+fn is_sidecar(process: &Process, binary: &Path) -> bool {
+    // Records from older versions may point at the login shell that used to wrap the sidecar
+    let binary_str = binary.to_string_lossy();
+    process.exe() == Some(binary)
+        || process
+            .cmd()
+            .iter()
+            .any(|arg| arg.to_string_lossy().contains(&*binary_str))
+}
+
+/// Records a freshly spawned sidecar as owned by this process.
+pub fn record_spawn(app: &AppHandle, pid: u32, port: Option<u16>) {
+    let Some(path) = registry_path(app) else {
+        return;
+    };
+
+    let mut system = System::new();
+
+    let Some(started_at) = load_process(&mut system, pid).map(|p| p.start_time()) else {
+        tracing::warn!(pid, "Sidecar exited before it could be recorded");
+        return;
+    };
+    let owner_pid = std::process::id();
+    let owner_started_at = load_process(&mut system, owner_pid)
+        .map(|p| p.start_time())
+        .unwrap_or_default();
+
+    let _guard = LOCK.lock().unwrap();
+    let mut records = read(&path);
+    records.retain(|r| r.pid != pid);
+    records.push(SidecarRecord {
+        pid,
+        port,
+        started_at,
+        binary: crate::cli::get_sidecar_path(app),
+        owner_pid,
+        owner_started_at,
+    });
+    write(&path, &records);
+
+    tracing::debug!(pid, ?port, "Recorded sidecar");
+}
+
+/// Forgets a sidecar once it has exited.
+pub fn record_exit(app: &AppHandle, pid: u32) {
+    let Some(path) = registry_path(app) else {
+        return;
+    };
+
+    let _guard = LOCK.lock().unwrap();
+    let mut records = read(&path);
+    let len = records.len();
+    records.retain(|r| r.pid != pid);
+    if records.len() != len {
+        write(&path, &records);
+    }
+}
+
+/// Kills sidecars left behind by previous runs of this app that crashed or were force quit.
+pub fn reap_orphans(app: &AppHandle) {
+    let Some(path) = registry_path(app) else {
+        return;
+    };
+
+    let _guard = LOCK.lock().unwrap();
+    let records = read(&path);
+    if records.is_empty() {
+        return;
+    }
+
+    let mut system = System::new();
+    let mut remaining = vec![];
+
+    for record in records {
+        if is_same_process(&mut system, record.owner_pid, record.owner_started_at) {
+            // Another instance is still running and owns this one
+            remaining.push(record);
+            continue;
+        }
+
+        let Some(process) = load_process(&mut system, record.pid) else {
+            tracing::debug!(pid = record.pid, "Recorded sidecar already exited");
+            continue;
+        };
+
+        if process.start_time() != record.started_at || !is_sidecar(process, &record.binary) {
+            tracing::debug!(
+                pid = record.pid,
+                "Recorded sidecar pid now belongs to another process"
+            );
+            continue;
+        }
+
+        if process.kill() {
+            tracing::info!(
+                pid = record.pid,
+                port = ?record.port,
+                owner_pid = record.owner_pid,
+                "Killed orphaned sidecar"
+            );
+        } else {
+            tracing::warn!(pid = record.pid, "Failed to kill orphaned sidecar");
+        }
+    }
+
+    write(&path, &remaining);
+}
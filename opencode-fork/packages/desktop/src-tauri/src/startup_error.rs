@@ -0,0 +1,373 @@
+//! Why the app couldn't get a server running at startup, for the frontend's error screen.
+
+use std::path::Path;
+
+use crate::{logging, ports, server::SpawnError, sidecar_log};
+
+/// How much of the sidecar's recent stderr is included.
+const STDERR_EXCERPT_LINES: usize = 40;
+
+/// How much of the app's own log is included, by lines and by bytes read from its end.
+const LOG_EXCERPT_LINES: usize = 40;
+const LOG_EXCERPT_BYTES: u64 = 64 * 1024;
+
+/// Lines the sidecar prints when it can't load the OpenCode config.
+const CONFIG_ERRORS: &[&str] = &[
+    "ConfigJsonError",
+    "ConfigInvalidError",
+    "ConfigDirectoryTypoError",
+    "ConfigFrontmatterError",
+    "Configuration is invalid",
+    "is not valid JSON(C)",
+    "This is a common typo.",
+    "Failed to parse YAML frontmatter",
+];
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StartupErrorReason {
+    /// The sidecar binary isn't next to the app, e.g. after a partial update.
+    SidecarMissing {
+        path: String,
+    },
+    SpawnFailed {
+        message: String,
+    },
+    /// The sidecar exited before it became healthy.
+    Exited {
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
+    HealthTimeout {
+        seconds: u32,
+    },
+    /// The port the sidecar should listen on is taken. `port` is unknown when no port in the
+    /// configured range was free.
+    PortConflict {
+        port: Option<u16>,
+        owner: Option<String>,
+    },
+    /// A server on the configured port requires credentials the app doesn't have.
+    AuthRejected {
+        url: String,
+    },
+    /// The sidecar couldn't load the OpenCode config.
+    ConfigInvalid {
+        message: String,
+    },
+    /// The configured server is up, but the loopback bridge the webview needs to reach it
+    /// couldn't be started.
+    BridgeFailed {
+        url: String,
+        message: String,
+    },
+}
+
+/// Something the user can do about a startup error. The frontend maps these to buttons where
+/// it can act on them directly.
+#[derive(Clone, Copy, PartialEq, Eq, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Remedy {
+    /// Relaunch the app and try again.
+    Retry,
+    /// Reinstall the app to restore its bundled files.
+    Reinstall,
+    /// Stop whatever holds the server port.
+    StopConflictingProcess,
+    /// Let the app pick a free server port.
+    UseAutomaticPort,
+    /// Fix the OpenCode config file named in the message.
+    FixConfig,
+    /// Undo extra arguments or environment variables given to the sidecar.
+    ResetLaunchOptions,
+    OpenLogs,
+}
+
+#[derive(Clone, serde::Serialize, specta::Type, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupError {
+    pub reason: StartupErrorReason,
+    pub message: String,
+    /// The last lines the sidecar wrote to stderr, oldest first.
+    pub stderr: Vec<String>,
+    /// The last lines of the app's own log, oldest first.
+    pub log: Vec<String>,
+    pub remedies: Vec<Remedy>,
+    pub log_path: Option<String>,
+    pub sidecar_log_path: Option<String>,
+}
+
+impl StartupError {
+    pub fn new(reason: StartupErrorReason) -> Self {
+        let message = match &reason {
+            StartupErrorReason::SidecarMissing { path } => {
+                format!("The OpenCode server binary is missing from {path}.")
+            }
+            StartupErrorReason::SpawnFailed { message } => {
+                format!("The OpenCode server couldn't be started: {message}")
+            }
+            StartupErrorReason::Exited { code, signal } => match (code, signal) {
+                (Some(code), _) => {
+                    format!("The OpenCode server exited with code {code} while starting.")
+                }
+                (None, Some(signal)) => {
+                    format!("The OpenCode server was stopped by signal {signal} while starting.")
+                }
+                (None, None) => "The OpenCode server exited while starting.".to_string(),
+            },
+            StartupErrorReason::HealthTimeout { seconds } => {
+                format!("The OpenCode server didn't respond within {seconds} seconds.")
+            }
+            StartupErrorReason::PortConflict {
+                port: Some(port),
+                owner,
+            } => {
+                let owner = owner
+                    .as_ref()
+                    .map(|owner| format!(" by {owner}"))
+                    .unwrap_or_default();
+                format!("Port {port} is already in use{owner}.")
+            }
+            StartupErrorReason::PortConflict { port: None, .. } => {
+                "No free port is available for the OpenCode server.".to_string()
+            }
+            StartupErrorReason::AuthRejected { url } => {
+                format!("The server at {url} requires credentials.")
+            }
+            StartupErrorReason::ConfigInvalid { message } => message.clone(),
+            StartupErrorReason::BridgeFailed { url, message } => {
+                format!("The connection to the server at {url} couldn't be set up: {message}")
+            }
+        };
+
+        let mut remedies = match &reason {
+            StartupErrorReason::SidecarMissing { .. } => vec![Remedy::Reinstall],
+            StartupErrorReason::SpawnFailed { .. } => vec![Remedy::Retry, Remedy::Reinstall],
+            StartupErrorReason::Exited { .. } => vec![Remedy::Retry, Remedy::ResetLaunchOptions],
+            StartupErrorReason::HealthTimeout { .. } => vec![Remedy::Retry],
+            StartupErrorReason::PortConflict { .. } | StartupErrorReason::AuthRejected { .. } => {
+                vec![Remedy::StopConflictingProcess, Remedy::UseAutomaticPort]
+            }
+            StartupErrorReason::ConfigInvalid { .. } => vec![Remedy::FixConfig, Remedy::Retry],
+            StartupErrorReason::BridgeFailed { .. } => vec![Remedy::Retry],
+        };
+        remedies.push(Remedy::OpenLogs);
+
+        let stderr = sidecar_log::recent_stderr();
+        let start = stderr.len().saturating_sub(STDERR_EXCERPT_LINES);
+
+        Self {
+            reason,
+            message,
+            stderr: stderr[start..]
+                .iter()
+                .map(|line| strip_ansi(line))
+                .collect(),
+            log: logging::path()
+                .and_then(|path| {
+                    logging::tail(&path, None, LOG_EXCERPT_BYTES, LOG_EXCERPT_LINES).ok()
+                })
+                .map(|lines| lines.into_iter().map(|line| line.text).collect())
+                .unwrap_or_default(),
+            remedies,
+            log_path: logging::path().map(|path| path.display().to_string()),
+            sidecar_log_path: sidecar_log::path().map(|path| path.display().to_string()),
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn sidecar_missing(path: &Path) -> Self {
+        Self::new(StartupErrorReason::SidecarMissing {
+            path: path.display().to_string(),
+        })
+    }
+
+    /// A taken port, with `message` explaining where it was configured.
+    pub fn port_conflict(port: Option<u16>, message: String) -> Self {
+        Self::new(StartupErrorReason::PortConflict {
+            port,
+            owner: port.and_then(ports::port_owner),
+        })
+        .with_message(message)
+    }
+
+    pub fn spawn_failed(message: impl Into<String>) -> Self {
+        Self::new(StartupErrorReason::SpawnFailed {
+            message: message.into(),
+        })
+    }
+}
+
+impl std::fmt::Display for StartupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// The config error the sidecar printed last, without terminal colours.
+fn config_error(stderr: &[String]) -> Option<String> {
+    stderr
+        .iter()
+        .rev()
+        .find(|line| CONFIG_ERRORS.iter().any(|marker| line.contains(marker)))
+        .map(|line| {
+            strip_ansi(line)
+                .trim()
+                .trim_start_matches("Error: ")
+                .to_string()
+        })
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI sequences end with a byte in @..~
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+impl From<SpawnError> for StartupError {
+    fn from(err: SpawnError) -> Self {
+        match err {
+            SpawnError::Missing(path) => Self::sidecar_missing(&path),
+            SpawnError::Spawn(message) | SpawnError::Failed(message) => Self::spawn_failed(message),
+            SpawnError::Exited { code, signal } => {
+                match config_error(&sidecar_log::recent_stderr()) {
+                    Some(message) => Self::new(StartupErrorReason::ConfigInvalid { message }),
+                    None => Self::new(StartupErrorReason::Exited { code, signal }),
+                }
+            }
+            SpawnError::TimedOut => Self::new(StartupErrorReason::HealthTimeout {
+                seconds: crate::server::HEALTH_CHECK_TIMEOUT.as_secs() as u32,
+            }),
+            SpawnError::PortInUse(port) => Self::new(StartupErrorReason::PortConflict {
+                port: Some(port),
+                owner: ports::port_owner(port),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(stderr: &[&str]) -> Vec<String> {
+        stderr.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn strips_colours() {
+        let cases = [
+            (
+                "\x1b[91m\x1b[1mError: \x1b[0mConfig file at /home/me/.config/opencode/opencode.json is not valid JSON(C)",
+                "Error: Config file at /home/me/.config/opencode/opencode.json is not valid JSON(C)",
+            ),
+            (
+                "\x1b[90m\x1b[1m↳ \x1b[0mInvalid input model",
+                "↳ Invalid input model",
+            ),
+            // Two-byte escapes like save cursor are dropped whole
+            ("\x1b7saved", "saved"),
+            // Cut off before its final byte
+            ("text\x1b[91", "text"),
+            ("no colours here", "no colours here"),
+            ("", ""),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(strip_ansi(line), expected, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn finds_config_errors() {
+        let cases = [
+            (
+                lines(&[
+                    "ERROR 2025-06-01T12:00:00 +41ms service=default name=ConfigJsonError data={\"path\":\"/home/me/.config/opencode/opencode.json\"} fatal",
+                    "\x1b[91m\x1b[1mError: \x1b[0mConfig file at /home/me/.config/opencode/opencode.json is not valid JSON(C): CloseBraceExpected at line 4, column 1",
+                ]),
+                "Config file at /home/me/.config/opencode/opencode.json is not valid JSON(C): CloseBraceExpected at line 4, column 1",
+            ),
+            (
+                lines(&[
+                    "ERROR 2025-06-01T12:00:00 +38ms service=default name=ConfigInvalidError fatal",
+                    "\x1b[91m\x1b[1mError: \x1b[0mConfiguration is invalid at /repo/opencode.json",
+                    "↳ Invalid input model",
+                    "↳ Unrecognized key: \"theeme\" ",
+                ]),
+                "Configuration is invalid at /repo/opencode.json",
+            ),
+            (
+                lines(&[
+                    "ERROR 2025-06-01T12:00:00 +12ms service=default name=ConfigDirectoryTypoError fatal",
+                    "\x1b[91m\x1b[1mError: \x1b[0mDirectory \"agents\" in /repo/.opencode is not valid. Rename the directory to \"agent\" or remove it. This is a common typo.",
+                ]),
+                "Directory \"agents\" in /repo/.opencode is not valid. Rename the directory to \"agent\" or remove it. This is a common typo.",
+            ),
+            (
+                lines(&[
+                    "ERROR 2025-06-01T12:00:00 +9ms service=default name=ConfigFrontmatterError fatal",
+                    "\x1b[91m\x1b[1mError: \x1b[0m/repo/.opencode/agent/review.md: Failed to parse YAML frontmatter: bad indentation of a mapping entry (3:7)",
+                ]),
+                "/repo/.opencode/agent/review.md: Failed to parse YAML frontmatter: bad indentation of a mapping entry (3:7)",
+            ),
+            // The formatted error can be missing if the sidecar was killed while printing it
+            (
+                lines(&[
+                    "INFO  2025-06-01T12:00:00 +2ms service=config path=/repo/opencode.json loading",
+                    "ERROR 2025-06-01T12:00:00 +38ms service=default name=ConfigInvalidError fatal",
+                ]),
+                "ERROR 2025-06-01T12:00:00 +38ms service=default name=ConfigInvalidError fatal",
+            ),
+        ];
+
+        for (stderr, expected) in cases {
+            assert_eq!(
+                config_error(&stderr).as_deref(),
+                Some(expected),
+                "{stderr:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ignores_other_errors() {
+        let cases = [
+            lines(&[]),
+            lines(&[
+                "INFO  2025-06-01T12:00:00 +0ms service=default version=1.0.0 args=[\"serve\"] opencode",
+                "INFO  2025-06-01T12:00:00 +2ms service=config path=/home/me/.config/opencode/opencode.json loading",
+            ]),
+            lines(&[
+                "\x1b[91m\x1b[1mError: \x1b[0mFailed to start server on port 4096",
+                "error: EADDRINUSE: address already in use 127.0.0.1:4096",
+            ]),
+            lines(&[
+                "\x1b[91m\x1b[1mError: \x1b[0mUnexpected error, check log file at /home/me/.local/share/opencode/log/2025-06-01T120000.log for more details",
+                "Cannot find module '@opencode-ai/plugin'",
+            ]),
+        ];
+
+        for stderr in cases {
+            assert_eq!(config_error(&stderr), None, "{stderr:?}");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Background tasks that stream updates to a webview over a `Channel`.
+//!
+//! A `Channel` doesn't report that the page behind it went away, and sending to it rarely fails
+//! after a reload, so these tasks can't rely on a failed send to stop. Each one is registered
+//! under an id that's returned to the frontend, and is aborted when the frontend unsubscribes or
+//! the webview that started it loads a new page.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use tauri::{State, Webview};
+use tokio::task::AbortHandle;
+
+struct Subscription {
+    webview: String,
+    task: AbortHandle,
+}
+
+#[derive(Default)]
+pub struct Subscriptions {
+    next_id: AtomicU32,
+    active: Arc<Mutex<HashMap<u32, Subscription>>>,
+}
+
+impl Subscriptions {
+    /// Runs `task` for `webview` until it finishes or is stopped, returning its id.
+    pub fn spawn<F>(&self, webview: &Webview, task: F) -> u32
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let active = self.active.clone();
+
+        // Held until the task is registered, so one that finishes right away can't be
+        // unregistered before that
+        let mut subscriptions = self.active.lock().unwrap();
+        let handle = tokio::spawn(async move {
+            task.await;
+            active.lock().unwrap().remove(&id);
+        });
+        subscriptions.insert(
+            id,
+            Subscription {
+                webview: webview.label().to_string(),
+                task: handle.abort_handle(),
+            },
+        );
+
+        id
+    }
+
+    /// Stops the subscription `id`. Returns whether it was still running.
+    pub fn stop(&self, id: u32) -> bool {
+        let subscription = self.active.lock().unwrap().remove(&id);
+        if let Some(subscription) = &subscription {
+            subscription.task.abort();
+        }
+        subscription.is_some()
+    }
+
+    /// Stops everything started by `webview`, for when it loads a new page.
+    pub fn stop_webview(&self, webview: &str) {
+        let mut active = self.active.lock().unwrap();
+        active.retain(|_, subscription| {
+            let keep = subscription.webview != webview;
+            if !keep {
+                subscription.task.abort();
+            }
+            keep
+        });
+    }
+}
+
+/// Stops a subscription started by one of the `watch_*` commands. Stopping one that already
+/// ended is fine.
+#[tauri::command]
+#[specta::specta]
+pub fn unsubscribe(state: State<'_, Subscriptions>, id: u32) {
+    if state.stop(id) {
+        tracing::debug!(id, "Stopped subscription");
+    }
+}
@@ -0,0 +1,138 @@
+//! How the app reaches its sidecar.
+//!
+//! On Linux and macOS the sidecar listens on a Unix socket inside a directory only the current
+//! user can enter, so other local users can't connect to it directly. The webview can only speak
+//! TCP, so it talks to a [`bridge`](crate::bridge) on 127.0.0.1 that forwards to the socket and
+//! only accepts the sidecar's password. Traffic to the bridge is still loopback TCP, which local
+//! proxies and other processes can see like they could the sidecar's port. TCP is used directly
+//! on Windows, when the user pinned a port, when no private directory is available, and when the
+//! sidecar fails to start on the socket.
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use tauri::{AppHandle, Manager};
+
+/// Where a sidecar listens.
+#[derive(Clone, Debug)]
+pub enum SidecarAddr {
+    Tcp {
+        hostname: String,
+        port: u16,
+    },
+    /// Listening on `socket`, reached through the bridge at `url`.
+    #[cfg(unix)]
+    Unix {
+        socket: PathBuf,
+        url: String,
+    },
+}
+
+impl SidecarAddr {
+    pub fn tcp(hostname: &str, port: u16) -> Self {
+        Self::Tcp {
+            hostname: hostname.to_string(),
+            port,
+        }
+    }
+
+    /// The URL HTTP clients in the app and the webview use.
+    pub fn url(&self) -> String {
+        match self {
+            Self::Tcp { hostname, port } => format!("http://{hostname}:{port}"),
+            #[cfg(unix)]
+            Self::Unix { url, .. } => url.clone(),
+        }
+    }
+
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            Self::Tcp { port, .. } => Some(*port),
+            #[cfg(unix)]
+            Self::Unix { .. } => None,
+        }
+    }
+
+    /// Arguments for `opencode serve`.
+    pub fn serve_args(&self) -> Vec<String> {
+        match self {
+            Self::Tcp { hostname, port } => vec![
+                "--hostname".to_string(),
+                hostname.clone(),
+                "--port".to_string(),
+                port.to_string(),
+            ],
+            #[cfg(unix)]
+            Self::Unix { socket, .. } => {
+                vec!["--unix-socket".to_string(), socket.display().to_string()]
+            }
+        }
+    }
+}
+
+/// `sun_path` is 108 bytes on Linux and 104 on macOS, including the terminating nul.
+#[cfg(unix)]
+const MAX_SOCKET_PATH: usize = 100;
+
+/// Creates `dir` if needed and checks that only the current user can access it.
+#[cfg(unix)]
+fn ensure_private_dir(dir: &Path) -> Result<(), String> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create {}: {}", dir.display(), e)),
+    }
+
+    let metadata = std::fs::symlink_metadata(dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    // SAFETY: geteuid has no preconditions and can't fail.
+    let uid = unsafe { libc::geteuid() };
+    if !metadata.is_dir() || metadata.uid() != uid {
+        return Err(format!(
+            "{} is not a directory owned by the current user",
+            dir.display()
+        ));
+    }
+
+    if metadata.permissions().mode() & 0o077 != 0 {
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {}", dir.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Picks a fresh socket path in the app's runtime dir, or `$TMPDIR` where there is none. The
+/// directory is named after the app identifier, so dev and release builds get separate ones.
+#[cfg(unix)]
+pub fn socket_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let base = app
+        .path()
+        .runtime_dir()
+        .unwrap_or_else(|_| std::env::temp_dir());
+    let dir = base.join(&app.config().identifier);
+
+    ensure_private_dir(&dir)?;
+
+    // Only one instance per identifier runs at a time, so any socket left here is from a crash
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            if entry.path().extension().is_some_and(|ext| ext == "sock") {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let path = dir.join(format!("sidecar-{}.sock", &id[..8]));
+
+    if path.as_os_str().len() > MAX_SOCKET_PATH {
+        return Err(format!("Socket path {} is too long", path.display()));
+    }
+
+    Ok(path)
+}
@@ -0,0 +1,126 @@
+import { Button } from "@opencode-ai/ui/button"
+import { RadioGroup } from "@opencode-ai/ui/radio-group"
+import { TextField } from "@opencode-ai/ui/text-field"
+import { message } from "@tauri-apps/plugin-dialog"
+import { relaunch } from "@tauri-apps/plugin-process"
+import { createResource, createSignal, For, Show } from "solid-js"
+
+import { commands, DiscoveredServer, ServerAuth } from "./bindings"
+import { toError } from "./command-error"
+import { t } from "./i18n"
+
+const AUTH_TYPES = ["basic", "bearer"] as const
+
+type Credentials = { type: ServerAuth["type"]; username: string; secret: string }
+
+// `opencode serve` uses this username unless OPENCODE_SERVER_USERNAME is set
+const DEFAULT_CREDENTIALS: Credentials = { type: "basic", username: "opencode", secret: "" }
+
+function toAuth(credentials: Credentials): ServerAuth | null {
+  if (!credentials.secret) return null
+  if (credentials.type === "bearer") return { type: "bearer", token: credentials.secret }
+  return { type: "basic", username: credentials.username.trim() || "opencode", password: credentials.secret }
+}
+
+// Lists servers already running on this machine and relaunches the app attached to the one picked
+export function AttachServer(props: { onClose: () => void }) {
+  const [servers, { refetch }] = createResource(() => commands.discoverServers().catch(() => []))
+  const [credentials, setCredentials] = createSignal<Record<string, Credentials>>({})
+  const [attaching, setAttaching] = createSignal<string>()
+
+  const credentialsFor = (server: DiscoveredServer) => credentials()[server.url] ?? DEFAULT_CREDENTIALS
+  const updateCredentials = (server: DiscoveredServer, update: Partial<Credentials>) =>
+    setCredentials((prev) => ({ ...prev, [server.url]: { ...credentialsFor(server), ...update } }))
+
+  const attach = async (server: DiscoveredServer) => {
+    setAttaching(server.url)
+    try {
+      await commands.attachServer(server.url, toAuth(credentialsFor(server)))
+      await commands.killSidecar().catch(() => undefined)
+      await relaunch()
+    } catch (e) {
+      await message(toError(e).message, { title: t("desktop.attachServer.failed.title") })
+    } finally {
+      setAttaching(undefined)
+    }
+  }
+
+  return (
+    <div class="fixed bottom-4 right-4 z-50 w-[32rem] max-h-[80vh] overflow-y-auto flex flex-col gap-4 p-4 rounded-md border border-border-base bg-background-base shadow-lg font-sans">
+      <div class="flex flex-col gap-1">
+        <h2 class="text-base font-medium text-text-strong">{t("desktop.attachServer.title")}</h2>
+        <p class="text-sm text-text-weak">{t("desktop.attachServer.description")}</p>
+      </div>
+      <Show
+        when={!servers.loading}
+        fallback={<p class="text-sm text-text-weak">{t("desktop.attachServer.searching")}</p>}
+      >
+        <Show when={servers()?.length} fallback={<p class="text-sm text-text-weak">{t("desktop.attachServer.none")}</p>}>
+          <ul class="flex flex-col gap-3">
+            <For each={servers()}>
+              {(server) => (
+                <li class="flex flex-col gap-2">
+                  <div class="flex flex-col">
+                    <span class="text-sm text-text-strong">
+                      {server.url}
+                      <Show when={server.version}>{(version) => ` · ${version()}`}</Show>
+                    </span>
+                    <Show when={server.directory}>
+                      {(directory) => <span class="text-xs text-text-weak truncate">{directory()}</span>}
+                    </Show>
+                  </div>
+                  <Show when={server.status.status === "unauthorized"}>
+                    <RadioGroup
+                      size="small"
+                      options={AUTH_TYPES}
+                      current={credentialsFor(server).type}
+                      label={(type) =>
+                        t(type === "basic" ? "desktop.attachServer.auth.basic" : "desktop.attachServer.auth.bearer")
+                      }
+                      onSelect={(type) => type && updateCredentials(server, { type })}
+                    />
+                    <Show when={credentialsFor(server).type === "basic"}>
+                      <TextField
+                        value={credentialsFor(server).username}
+                        onChange={(username: string) => updateCredentials(server, { username })}
+                        label={t("desktop.attachServer.username")}
+                      />
+                    </Show>
+                    <TextField
+                      type="password"
+                      value={credentialsFor(server).secret}
+                      onChange={(secret: string) => updateCredentials(server, { secret })}
+                      label={t(
+                        credentialsFor(server).type === "basic"
+                          ? "desktop.attachServer.password"
+                          : "desktop.attachServer.token",
+                      )}
+                    />
+                  </Show>
+                  <div class="flex items-center gap-2">
+                    <Button
+                      size="small"
+                      variant="secondary"
+                      disabled={attaching() !== undefined}
+                      onClick={() => attach(server)}
+                    >
+                      {t("desktop.attachServer.attach")}
+                    </Button>
+                  </div>
+                </li>
+              )}
+            </For>
+          </ul>
+        </Show>
+      </Show>
+      <div class="flex justify-end gap-2">
+        <Button size="small" variant="ghost" onClick={() => refetch()}>
+          {t("desktop.attachServer.refresh")}
+        </Button>
+        <Button size="small" onClick={props.onClose}>
+          {t("desktop.attachServer.close")}
+        </Button>
+      </div>
+    </div>
+  )
+}
@@ -6,10 +6,50 @@ import * as __TAURI_EVENT from "@tauri-apps/api/event";
 /** Commands */
 export const commands = {
 	killSidecar: () => __TAURI_INVOKE<void>("kill_sidecar"),
+	restartSidecar: () => __TAURI_INVOKE<null>("restart_sidecar"),
+	getShutdownGracePeriod: () => __TAURI_INVOKE<number>("get_shutdown_grace_period"),
+	setShutdownGracePeriod: (gracePeriodMs: number) => __TAURI_INVOKE<null>("set_shutdown_grace_period", { gracePeriodMs }),
 	installCli: () => __TAURI_INVOKE<string>("install_cli"),
 	awaitInitialization: (events: Channel) => __TAURI_INVOKE<ServerReadyData>("await_initialization", { events }),
 	getDefaultServerUrl: () => __TAURI_INVOKE<string | null>("get_default_server_url"),
 	setDefaultServerUrl: (url: string | null) => __TAURI_INVOKE<null>("set_default_server_url", { url }),
+	listServerProfiles: () => __TAURI_INVOKE<ServerProfile[]>("list_server_profiles"),
+	createServerProfile: (profile: ServerProfileInput) => __TAURI_INVOKE<ServerProfile>("create_server_profile", { profile }),
+	updateServerProfile: (id: string, profile: ServerProfileInput) => __TAURI_INVOKE<ServerProfile>("update_server_profile", { id, profile }),
+	deleteServerProfile: (id: string) => __TAURI_INVOKE<null>("delete_server_profile", { id }),
+	testServerProfile: (profile: ServerProfileInput) => __TAURI_INVOKE<HealthStatus>("test_server_profile", { profile }),
+	discoverServers: () => __TAURI_INVOKE<DiscoveredServer[]>("discover_servers"),
+	attachServer: (url: string, auth: ServerAuth | null) => __TAURI_INVOKE<ServerProfile>("attach_server", { url, auth }),
+	watchServerHealth: (events: Channel) => __TAURI_INVOKE<number>("watch_server_health", { events }),
+	unsubscribe: (id: number) => __TAURI_INVOKE<null>("unsubscribe", { id }),
+	getHealthCheckInterval: () => __TAURI_INVOKE<number>("get_health_check_interval"),
+	setHealthCheckInterval: (intervalMs: number) => __TAURI_INVOKE<null>("set_health_check_interval", { intervalMs }),
+	getSidecarPortSetting: () => __TAURI_INVOKE<SidecarPortSetting>("get_sidecar_port_setting"),
+	setSidecarPortSetting: (setting: SidecarPortSetting) => __TAURI_INVOKE<null>("set_sidecar_port_setting", { setting }),
+	getSidecarResourceLimits: () => __TAURI_INVOKE<ResourceLimits>("get_sidecar_resource_limits"),
+	setSidecarResourceLimits: (limits: ResourceLimits) => __TAURI_INVOKE<null>("set_sidecar_resource_limits", { limits }),
+	getSidecarResourceUsage: () => __TAURI_INVOKE<ResourceUsage | null>("get_sidecar_resource_usage"),
+	getSidecarLaunchOptions: () => __TAURI_INVOKE<SidecarLaunchOptions>("get_sidecar_launch_options"),
+	setSidecarLaunchOptions: (options: SidecarLaunchOptions) => __TAURI_INVOKE<boolean>("set_sidecar_launch_options", { options }),
+	getShellEnvDiagnostics: () => __TAURI_INVOKE<ShellEnvDiagnostics>("get_shell_env_diagnostics"),
+	refreshShellEnv: () => __TAURI_INVOKE<ShellEnvDiagnostics>("refresh_shell_env"),
+	getSidecarOutput: (limit: number | null) => __TAURI_INVOKE<SidecarLogLine[]>("get_sidecar_output", { limit }),
+	getLogLevels: () => __TAURI_INVOKE<LogLevels>("get_log_levels"),
+	setDesktopLogFilter: (filter: string | null) => __TAURI_INVOKE<null>("set_desktop_log_filter", { filter }),
+	setSidecarLogLevel: (level: SidecarLogLevel, restart: boolean) => __TAURI_INVOKE<null>("set_sidecar_log_level", { level, restart }),
+	startDebugLogging: (minutes: number) => __TAURI_INVOKE<null>("start_debug_logging", { minutes }),
+	stopDebugLogging: () => __TAURI_INVOKE<null>("stop_debug_logging"),
+	listLogFiles: () => __TAURI_INVOKE<LogFileInfo[]>("list_log_files"),
+	readLogFile: (name: string, filter: LogFilter, offset: number | null, limit: number) => __TAURI_INVOKE<LogPage>("read_log_file", { name, filter, offset, limit }),
+	watchLogFile: (name: string, filter: LogFilter, events: Channel) => __TAURI_INVOKE<number>("watch_log_file", { name, filter, events }),
+	tailLogFile: (name: string, filter: LogFilter, before: number | null, limit: number) => __TAURI_INVOKE<LogPage>("tail_log_file", { name, filter, before, limit }),
+	getLogRetention: () => __TAURI_INVOKE<LogRetention>("get_log_retention"),
+	setLogRetention: (retention: LogRetention) => __TAURI_INVOKE<null>("set_log_retention", { retention }),
+	exportDiagnostics: () => __TAURI_INVOKE<string | null>("export_diagnostics"),
+	listCrashReports: () => __TAURI_INVOKE<CrashReportSummary[]>("list_crash_reports"),
+	readCrashReport: (id: string) => __TAURI_INVOKE<string>("read_crash_report", { id }),
+	saveCrashReport: (id: string) => __TAURI_INVOKE<string | null>("save_crash_report", { id }),
+	deleteCrashReport: (id: string) => __TAURI_INVOKE<null>("delete_crash_report", { id }),
 	getDisplayBackend: () => __TAURI_INVOKE<"wayland" | "auto" | null>("get_display_backend"),
 	setDisplayBackend: (backend: LinuxDisplayBackend) => __TAURI_INVOKE<null>("set_display_backend", { backend }),
 	parseMarkdownCommand: (markdown: string) => __TAURI_INVOKE<string>("parse_markdown_command", { markdown }),
@@ -20,20 +60,541 @@ export const commands = {
 /** Events */
 export const events = {
 	loadingWindowComplete: makeEvent<LoadingWindowComplete>("loading-window-complete"),
+	serverStatusChanged: makeEvent<ServerStatusChanged>("server-status-changed"),
+	shutdownProgress: makeEvent<ShutdownProgress>("shutdown-progress"),
+	resourceLimitHit: makeEvent<ResourceLimitHit>("resource-limit-hit"),
 };
 
 /* Types */
-export type InitStep = { phase: "server_waiting" } | { phase: "sqlite_waiting" } | { phase: "done" };
+export type CommandError = {
+		kind: CommandErrorKind,
+		/**
+		 * Description of the error that can be shown as is.
+		 */
+		message: string,
+	};
+
+export type CommandErrorKind = 
+/**
+ * The settings store couldn't be opened, read or saved.
+ */
+{ code: "settings" } | 
+/**
+ * An argument was rejected. `field` is the name of the offending input, as the frontend
+ * knows it.
+ */
+{ code: "invalid_input", field: string } | { code: "invalid_url", url: string } | 
+/**
+ * No server profile has the given id.
+ */
+{ code: "profile_not_found", id: string } | 
+/**
+ * The command isn't available on this platform.
+ */
+{ code: "unsupported", platform: string } | 
+/**
+ * The sidecar binary isn't next to the app.
+ */
+{ code: "sidecar_missing", path: string } | 
+/**
+ * The app doesn't run a local server, e.g. because it's connected to a remote one.
+ */
+{ code: "server_not_running" } | 
+/**
+ * The local server is being shut down.
+ */
+{ code: "server_stopping" } | 
+/**
+ * A file couldn't be read or written.
+ */
+{ code: "io", path: string } | 
+/**
+ * A helper process ran but failed. `status` is its exit code.
+ */
+{ code: "process_failed", status: number | null, stderr: string } | 
+/**
+ * Anything the frontend has no reason to tell apart.
+ */
+{ code: "internal" };
+
+export type ConnectionState = "connected" | "degraded" | "disconnected";
+
+export type CrashReportSummary = {
+		id: string,
+		/**
+		 * RFC 3339.
+		 */
+		timestamp: string,
+		/**
+		 * The panic message.
+		 */
+		message: string,
+		saved: boolean,
+	};
+
+export type DiscoveredServer = {
+		url: string,
+		port: number,
+		pid: number | null,
+		version: string | null,
+		/**
+		 * Working directory the server was started in, if it published one.
+		 */
+		directory: string | null,
+		source: DiscoverySource,
+		status: HealthStatus,
+	};
+
+export type DiscoverySource = "file" | "socket";
+
+export type Enforcement = 
+/**
+ * No limits are configured, or the platform doesn't support them.
+ */
+"none" | "cgroup" | 
+/**
+ * Per-process rlimits only, the CPU limit isn't applied.
+ */
+"rlimit";
+
+export type HealthStatus = { status: "healthy" } | { status: "unauthorized", code: number } | { status: "unhealthy", code: number } | { status: "unreachable", message: string } | { status: "untrusted_certificate", message: string };
+
+export type InitPhase = 
+/**
+ * Looking up the default server profile and the CLI config.
+ */
+{ type: "resolving_config" } | 
+/**
+ * Checking the health of a configured server.
+ */
+{ type: "connecting_remote", url: string } | 
+/**
+ * Running the login shell to capture its environment.
+ */
+{ type: "resolving_shell_env" } | { type: "spawning" } | 
+/**
+ * Waiting for the sidecar to answer its health endpoint.
+ */
+{ type: "health_checking", attempt: number } | 
+/**
+ * The sidecar is migrating its database. Progress is known once it reports a migration.
+ */
+{ type: "migrating", current: number | null, total: number | null } | { type: "done" };
+
+export type InitStep = {
+		phase: InitPhase,
+		/**
+		 * Time since startup began.
+		 */
+		elapsedMs: number,
+		/**
+		 * Time spent in the current phase.
+		 */
+		phaseElapsedMs: number,
+	};
+
+export type LimitedResource = "memory" | "cpu" | "processes";
 
 export type LinuxDisplayBackend = "wayland" | "auto";
 
 export type LoadingWindowComplete = null;
 
+export type LogEntry = {
+		/**
+		 * Byte offset of the line in its file, uncompressed.
+		 */
+		offset: number,
+		/**
+		 * RFC 3339.
+		 */
+		timestamp: string | null,
+		level: LogLevel | null,
+		/**
+		 * The tracing target, or the service for sidecar logs.
+		 */
+		target: string | null,
+		/**
+		 * The line without its timestamp, level and target.
+		 */
+		message: string,
+		line: string,
+	};
+
+export type LogFileInfo = {
+		name: string,
+		source: LogSource,
+		/**
+		 * Size on disk, compressed for rotated files.
+		 */
+		size: number,
+		/**
+		 * Whether the file was rotated and gzipped.
+		 */
+		compressed: boolean,
+		/**
+		 * RFC 3339.
+		 */
+		modified: string | null,
+		/**
+		 * Whether this run of the app is writing to the file.
+		 */
+		current: boolean,
+	};
+
+export type LogFilter = {
+		/**
+		 * The least severe level to include.
+		 */
+		minLevel: LogLevel | null,
+		/**
+		 * Start of the target, e.g. `opencode_lib::server`. For sidecar logs it's matched against
+		 * the service instead.
+		 */
+		target: string | null,
+		/**
+		 * RFC 3339, inclusive.
+		 */
+		since: string | null,
+		/**
+		 * RFC 3339, exclusive.
+		 */
+		until: string | null,
+		/**
+		 * Text the line must contain, ignoring case.
+		 */
+		text: string | null,
+	};
+
+export type LogLevel = "trace" | "debug" | "info" | "warn" | "error";
+
+export type LogLevels = {
+		/**
+		 * The desktop app's filter, in `RUST_LOG` syntax.
+		 */
+		desktopFilter: string,
+		/**
+		 * The level the sidecar is started with.
+		 */
+		sidecarLevel: SidecarLogLevel,
+		/**
+		 * Seconds left until debug logging ends, if it's on.
+		 */
+		debugRemainingSecs: number | null,
+	};
+
+export type LogPage = {
+		entries: LogEntry[],
+		/**
+		 * Where the next page starts. Unset once the end of the file is reached.
+		 */
+		nextOffset: number | null,
+		/**
+		 * Size on disk, compressed for rotated files.
+		 */
+		size: number,
+	};
+
+/**
+ * How much log history is kept. Rotation takes effect the next time the app starts.
+ */
+export type LogRetention = {
+		/**
+		 * Files older than this are deleted.
+		 */
+		maxAgeDays: number,
+		/**
+		 * The oldest files are deleted while all of them together are larger than this.
+		 */
+		maxTotalMb: number,
+		/**
+		 * Size at which a log file is closed, compressed and a new one started.
+		 */
+		maxFileMb: number,
+	};
+
+export type LogSource = "desktop" | "sidecar";
+
+/**
+ * Emitted when the sidecar runs into one of its limits, with the total number of hits so far.
+ */
+/**
+ * Something the user can do about a startup error. The frontend maps these to buttons where
+ * it can act on them directly.
+ */
+export type Remedy = 
+/**
+ * Relaunch the app and try again.
+ */
+"retry" | 
+/**
+ * Reinstall the app to restore its bundled files.
+ */
+"reinstall" | 
+/**
+ * Stop whatever holds the server port.
+ */
+"stop_conflicting_process" | 
+/**
+ * Let the app pick a free server port.
+ */
+"use_automatic_port" | 
+/**
+ * Fix the OpenCode config file named in the message.
+ */
+"fix_config" | 
+/**
+ * Undo extra arguments or environment variables given to the sidecar.
+ */
+"reset_launch_options" | "open_logs";
+
+export type ResourceLimitHit = {
+		resource: LimitedResource,
+		count: number,
+	};
+
+export type ResourceLimits = {
+		memoryMb: number | null,
+		/**
+		 * Share of one core, e.g. 200 for two full cores.
+		 */
+		cpuPercent: number | null,
+		maxProcesses: number | null,
+	};
+
+export type ResourceUsage = {
+		enforcement: Enforcement,
+		limits: ResourceLimits,
+		memoryMb: number,
+		/**
+		 * Share of one core since the previous sample.
+		 */
+		cpuPercent: number,
+		processes: number,
+		/**
+		 * How often each limit was hit. Only known when enforced through a cgroup.
+		 */
+		memoryLimitHits: number | null,
+		oomKills: number | null,
+		cpuThrottled: number | null,
+		processLimitHits: number | null,
+	};
+
+export type ServerAuth = { type: "basic", username: string, password: string } | { type: "bearer", token: string };
+
+export type ServerHealth = {
+		state: ConnectionState,
+		status: HealthStatus,
+		/**
+		 * Round trip of the last check, if the server answered at all.
+		 */
+		latencyMs: number | null,
+		consecutiveFailures: number,
+	};
+
+export type ServerPhase = "starting" | "running" | "restarting" | "failed" | "stopping" | "stopped";
+
+export type ServerProfile = {
+		id: string,
+		name: string,
+		url: string,
+		auth: ServerAuth | null,
+		tls: ServerTls | null,
+		isDefault: boolean,
+	};
+
+export type ServerProfileInput = {
+		name: string,
+		url: string,
+		auth: ServerAuth | null,
+		tls: ServerTls | null,
+		isDefault: boolean,
+	};
+
 export type ServerReadyData = {
 		url: string,
-		password: string | null,
+		auth: ServerAuth | null,
 	};
 
+export type ServerStatusChanged = {
+		phase: ServerPhase,
+		attempt: number,
+		lastExit: SidecarExit | null,
+	};
+
+export type ServerTls = {
+		/**
+		 * Extra PEM-encoded root certificates to trust for this server.
+		 */
+		caCertificates: string[],
+		/**
+		 * Hex-encoded SHA-256 fingerprint of the server's leaf certificate.
+		 */
+		pinnedFingerprint: string | null,
+		/**
+		 * PEM-encoded client certificate chain for mutual TLS.
+		 */
+		clientCertificate: string | null,
+		/**
+		 * PEM-encoded private key matching `client_certificate`.
+		 */
+		clientKey: string | null,
+	};
+
+export type ShellEnvDiagnostics = {
+		shell: string | null,
+		status: ShellEnvStatus,
+		error: string | null,
+		durationMs: number | null,
+		/**
+		 * Variables the shell added or changed. Values are left out as they may hold secrets.
+		 */
+		fromShell: ShellEnvVar[],
+	};
+
+export type ShellEnvStatus = "captured" | "failed" | 
+/**
+ * Windows, where apps already get the user's environment.
+ */
+"unsupported";
+
+export type ShellEnvVar = {
+		name: string,
+		/**
+		 * Whether the app's own environment had a different value, rather than none at all.
+		 */
+		overrides: boolean,
+	};
+
+export type ShutdownProgress = {
+		step: ShutdownStep,
+		elapsedMs: number,
+		gracePeriodMs: number,
+	};
+
+export type ShutdownStep = 
+/**
+ * Asking the server to dispose its instances so pending writes are flushed.
+ */
+"disposing" | 
+/**
+ * Signalled the sidecar to exit and waiting for it to do so.
+ */
+"waiting" | 
+/**
+ * The grace period ran out and the sidecar is being killed.
+ */
+"force_killing" | "exited";
+
+export type SidecarExit = {
+		code: number | null,
+		signal: number | null,
+	};
+
+/**
+ * How the sidecar is launched. Changes apply the next time it starts, see
+ * [`restart_sidecar`](crate::server::restart_sidecar).
+ */
+export type SidecarLaunchOptions = {
+		logLevel: SidecarLogLevel,
+		/**
+		 * `OPENCODE_EXPERIMENTAL_ICON_DISCOVERY`
+		 */
+		iconDiscovery: boolean,
+		/**
+		 * `OPENCODE_EXPERIMENTAL_FILEWATCHER`
+		 */
+		fileWatcher: boolean,
+		/**
+		 * Extra environment variables, such as provider endpoints or feature flags.
+		 */
+		env: Partial<{ [key in string]: string }>,
+		/**
+		 * Extra arguments passed to `opencode serve`.
+		 */
+		args: string[],
+	};
+
+export type SidecarLogLevel = "debug" | "info" | "warn" | "error";
+
+export type SidecarLogLine = {
+		pid: number,
+		stream: SidecarStream,
+		/**
+		 * Set for lines written by the sidecar's logger, unset for other output such as stack
+		 * traces.
+		 */
+		level: SidecarLogLevel | null,
+		/**
+		 * As printed by the sidecar, in UTC without fractional seconds.
+		 */
+		timestamp: string | null,
+		service: string | null,
+		/**
+		 * The line without its level and timing.
+		 */
+		message: string,
+		/**
+		 * The line as printed.
+		 */
+		line: string,
+	};
+
+export type SidecarPortSetting = 
+/**
+ * Any free port picked by the OS.
+ */
+{ mode: "auto" } | { mode: "fixed", port: number } | 
+/**
+ * The first free port in `start..=end`.
+ */
+{ mode: "range", start: number, end: number };
+
+export type SidecarStream = "stdout" | "stderr";
+
+export type StartupError = {
+		reason: StartupErrorReason,
+		message: string,
+		/**
+		 * The last lines the sidecar wrote to stderr, oldest first.
+		 */
+		stderr: string[],
+		/**
+		 * The last lines of the app's own log, oldest first.
+		 */
+		log: string[],
+		remedies: Remedy[],
+		logPath: string | null,
+		sidecarLogPath: string | null,
+	};
+
+export type StartupErrorReason = 
+/**
+ * The sidecar binary isn't next to the app, e.g. after a partial update.
+ */
+{ kind: "sidecar_missing", path: string } | { kind: "spawn_failed", message: string } | 
+/**
+ * The sidecar exited before it became healthy.
+ */
+{ kind: "exited", code: number | null, signal: number | null } | { kind: "health_timeout", seconds: number } | 
+/**
+ * The port the sidecar should listen on is taken. `port` is unknown when no port in the
+ * configured range was free.
+ */
+{ kind: "port_conflict", port: number | null, owner: string | null } | 
+/**
+ * A server on the configured port requires credentials the app doesn't have.
+ */
+{ kind: "auth_rejected", url: string } | 
+/**
+ * The sidecar couldn't load the OpenCode config.
+ */
+{ kind: "config_invalid", message: string } | 
+/**
+ * The configured server is up, but the loopback bridge the webview needs to reach it
+ * couldn't be started.
+ */
+{ kind: "bridge_failed", url: string, message: string };
+
 /* Tauri Specta runtime */
 function makeEvent<T>(name: string) {
     const base = {
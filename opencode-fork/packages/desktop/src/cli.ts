@@ -2,6 +2,7 @@ import { message } from "@tauri-apps/plugin-dialog"
 
 import { initI18n, t } from "./i18n"
 import { commands } from "./bindings"
+import { toError } from "./command-error"
 
 export async function installCli(): Promise<void> {
   await initI18n()
@@ -10,6 +11,6 @@ export async function installCli(): Promise<void> {
     const path = await commands.installCli()
     await message(t("desktop.cli.installed.message", { path }), { title: t("desktop.cli.installed.title") })
   } catch (e) {
-    await message(t("desktop.cli.failed.message", { error: String(e) }), { title: t("desktop.cli.failed.title") })
+    await message(t("desktop.cli.failed.message", { error: toError(e).message }), { title: t("desktop.cli.failed.title") })
   }
 }
@@ -0,0 +1,12 @@
+import type { CommandError } from "./bindings"
+
+export function isCommandError(error: unknown): error is CommandError {
+  return typeof error === "object" && error !== null && "kind" in error && "message" in error
+}
+
+// Commands reject with a plain object, shared code expects an Error with a readable message
+export function toError(error: unknown) {
+  if (error instanceof Error) return error
+  if (isCommandError(error)) return new Error(error.message, { cause: error })
+  return new Error(String(error))
+}
@@ -0,0 +1,97 @@
+import { Button } from "@opencode-ai/ui/button"
+import { TextField } from "@opencode-ai/ui/text-field"
+import { message } from "@tauri-apps/plugin-dialog"
+import { createResource, createSignal, For, Show } from "solid-js"
+
+import { commands } from "./bindings"
+import { toError } from "./command-error"
+import { t } from "./i18n"
+
+const run = async (action: () => Promise<unknown>) => {
+  try {
+    await action()
+  } catch (e) {
+    await message(toError(e).message, { title: t("desktop.crashReports.failed.title") })
+  }
+}
+
+// Offers the crash reports of earlier runs that haven't been saved yet
+export function CrashReports() {
+  const [reports, { refetch }] = createResource(() =>
+    commands
+      .listCrashReports()
+      .then((reports) => reports.filter((report) => !report.saved))
+      .catch(() => []),
+  )
+  const [dismissed, setDismissed] = createSignal(false)
+  const [viewing, setViewing] = createSignal<{ id: string; text: string }>()
+
+  const view = (id: string) =>
+    run(async () => {
+      if (viewing()?.id === id) return setViewing(undefined)
+      setViewing({ id, text: await commands.readCrashReport(id) })
+    })
+
+  const save = (id: string) =>
+    run(async () => {
+      if (await commands.saveCrashReport(id)) await refetch()
+    })
+
+  const remove = (id: string) =>
+    run(async () => {
+      await commands.deleteCrashReport(id)
+      if (viewing()?.id === id) setViewing(undefined)
+      await refetch()
+    })
+
+  return (
+    <Show when={!dismissed() && reports()?.length}>
+      <div class="fixed bottom-4 right-4 z-50 w-[32rem] max-h-[80vh] overflow-y-auto flex flex-col gap-4 p-4 rounded-md border border-border-base bg-background-base shadow-lg font-sans">
+        <div class="flex flex-col gap-1">
+          <h2 class="text-base font-medium text-text-strong">{t("desktop.crashReports.title")}</h2>
+          <p class="text-sm text-text-weak">{t("desktop.crashReports.description")}</p>
+        </div>
+        <ul class="flex flex-col gap-3">
+          <For each={reports()}>
+            {(item) => (
+              <li class="flex flex-col gap-2">
+                <div class="flex flex-col">
+                  <span class="text-sm text-text-strong">{new Date(item.timestamp).toLocaleString()}</span>
+                  <span class="text-xs text-text-weak truncate">{item.message}</span>
+                </div>
+                <div class="flex items-center gap-2">
+                  <Button size="small" variant="ghost" onClick={() => view(item.id)}>
+                    {viewing()?.id === item.id ? t("desktop.crashReports.hide") : t("desktop.crashReports.view")}
+                  </Button>
+                  <Button size="small" variant="ghost" onClick={() => save(item.id)}>
+                    {t("desktop.crashReports.save")}
+                  </Button>
+                  <Button size="small" variant="ghost" onClick={() => remove(item.id)}>
+                    {t("desktop.crashReports.delete")}
+                  </Button>
+                </div>
+                <Show when={viewing()?.id === item.id ? viewing() : undefined}>
+                  {(viewed) => (
+                    <TextField
+                      value={viewed().text}
+                      readOnly
+                      copyable
+                      multiline
+                      class="max-h-64 w-full font-mono text-xs no-scrollbar"
+                      label={t("desktop.crashReports.report")}
+                    />
+                  )}
+                </Show>
+              </li>
+            )}
+          </For>
+        </ul>
+        <div class="flex justify-end">
+          <Button size="small" onClick={() => setDismissed(true)}>
+            {t("desktop.crashReports.dismiss")}
+          </Button>
+        </div>
+      </div>
+    </Show>
+  )
+}
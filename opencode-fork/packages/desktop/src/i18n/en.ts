@@ -24,4 +24,49 @@ export const dict = {
   "desktop.cli.installed.message": "CLI installed to {{path}}\n\nRestart your terminal to use the 'opencode' command.",
   "desktop.cli.failed.title": "Installation Failed",
   "desktop.cli.failed.message": "Failed to install CLI: {{error}}",
+
+  "desktop.startupError.title": "OpenCode couldn't start its server",
+  "desktop.startupError.output": "Server output",
+  "desktop.startupError.log": "App log",
+  "desktop.startupError.remedy.retry": "Restart OpenCode to try again.",
+  "desktop.startupError.remedy.reinstall": "Reinstall OpenCode to restore its bundled files.",
+  "desktop.startupError.remedy.stop_conflicting_process": "Stop the program that is using the port.",
+  "desktop.startupError.remedy.use_automatic_port": "Let OpenCode pick a free port.",
+  "desktop.startupError.remedy.fix_config": "Fix the OpenCode config file mentioned above.",
+  "desktop.startupError.remedy.reset_launch_options": "Remove extra server arguments and environment variables.",
+  "desktop.startupError.remedy.open_logs": "Check the logs for details.",
+  "desktop.startupError.action.retry": "Restart",
+  "desktop.startupError.action.reinstall": "Reinstall",
+  "desktop.startupError.action.stop_conflicting_process": "Stop Process",
+  "desktop.startupError.action.use_automatic_port": "Use Automatic Port",
+  "desktop.startupError.action.fix_config": "Fix Config",
+  "desktop.startupError.action.reset_launch_options": "Reset Launch Options",
+  "desktop.startupError.action.open_logs": "Open Logs",
+
+  "desktop.crashReports.title": "OpenCode quit unexpectedly",
+  "desktop.crashReports.description":
+    "Crash reports were saved from earlier runs. Save them to attach to a bug report, or delete them.",
+  "desktop.crashReports.report": "Crash report",
+  "desktop.crashReports.view": "View",
+  "desktop.crashReports.hide": "Hide",
+  "desktop.crashReports.save": "Save...",
+  "desktop.crashReports.delete": "Delete",
+  "desktop.crashReports.dismiss": "Not Now",
+  "desktop.crashReports.failed.title": "Crash Report",
+
+  "desktop.attachServer.command": "Attach to Running Server...",
+  "desktop.attachServer.title": "Running servers",
+  "desktop.attachServer.description":
+    "OpenCode servers already running on this computer. Attaching restarts the app connected to the one you pick.",
+  "desktop.attachServer.searching": "Looking for servers...",
+  "desktop.attachServer.none": "No running servers were found.",
+  "desktop.attachServer.auth.basic": "Password",
+  "desktop.attachServer.auth.bearer": "Token",
+  "desktop.attachServer.username": "Username",
+  "desktop.attachServer.password": "Server password",
+  "desktop.attachServer.token": "Bearer token",
+  "desktop.attachServer.attach": "Attach",
+  "desktop.attachServer.refresh": "Search Again",
+  "desktop.attachServer.close": "Close",
+  "desktop.attachServer.failed.title": "Attach Failed",
 }
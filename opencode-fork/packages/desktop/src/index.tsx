@@ -30,9 +30,13 @@ import { UPDATER_ENABLED } from "./updater"
 import { initI18n, t } from "./i18n"
 import pkg from "../package.json"
 import "./styles.css"
-import { commands, InitStep } from "./bindings"
+import { commands, InitStep, ServerAuth } from "./bindings"
 import { Channel } from "@tauri-apps/api/core"
 import { createMenu } from "./menu"
+import { isStartupError, StartupErrorScreen } from "./startup-error"
+import { CrashReports } from "./crash-reports"
+import { AttachServer } from "./attach-server"
+import { toError } from "./command-error"
 
 const root = document.getElementById("root")
 if (import.meta.env.DEV && !(root instanceof HTMLElement)) {
@@ -59,7 +63,10 @@ const listenForDeepLinks = async () => {
   await onOpenUrl((urls) => emitDeepLinks(urls)).catch(() => undefined)
 }
 
-const createPlatform = (password: Accessor<string | null>): Platform => ({
+const authorizationHeader = (auth: ServerAuth) =>
+  auth.type === "basic" ? `Basic ${btoa(`${auth.username}:${auth.password}`)}` : `Bearer ${auth.token}`
+
+const createPlatform = (auth: Accessor<ServerAuth | null>): Platform => ({
   platform: "desktop",
   os: (() => {
     const type = ostype()
@@ -323,18 +330,18 @@ const createPlatform = (password: Accessor<string | null>): Platform => ({
   },
 
   fetch: (input, init) => {
-    const pw = password()
+    const current = auth()
 
-    const addHeader = (headers: Headers, password: string) => {
-      headers.append("Authorization", `Basic ${btoa(`opencode:${password}`)}`)
+    const addHeader = (headers: Headers, auth: ServerAuth) => {
+      headers.append("Authorization", authorizationHeader(auth))
     }
 
     if (input instanceof Request) {
-      if (pw) addHeader(input.headers, pw)
+      if (current) addHeader(input.headers, current)
       return tauriFetch(input)
     } else {
       const headers = new Headers(init?.headers)
-      if (pw) addHeader(headers, pw)
+      if (current) addHeader(headers, current)
       return tauriFetch(input, {
         ...(init as any),
         headers: headers,
@@ -348,7 +355,9 @@ const createPlatform = (password: Accessor<string | null>): Platform => ({
   },
 
   setDefaultServerUrl: async (url: string | null) => {
-    await commands.setDefaultServerUrl(url)
+    await commands.setDefaultServerUrl(url).catch((e) => {
+      throw toError(e)
+    })
   },
 
   getDisplayBackend: async () => {
@@ -399,8 +408,9 @@ createMenu((id) => {
 void listenForDeepLinks()
 
 render(() => {
-  const [serverPassword, setServerPassword] = createSignal<string | null>(null)
-  const platform = createPlatform(() => serverPassword())
+  const [serverAuth, setServerAuth] = createSignal<ServerAuth | null>(null)
+  const [attaching, setAttaching] = createSignal(false)
+  const platform = createPlatform(() => serverAuth())
 
   function handleClick(e: MouseEvent) {
     const link = (e.target as HTMLElement).closest("a.external-link") as HTMLAnchorElement | null
@@ -420,17 +430,33 @@ render(() => {
   return (
     <PlatformProvider value={platform}>
       <AppBaseProviders>
+        <CrashReports />
+        <Show when={attaching()}>
+          <AttachServer onClose={() => setAttaching(false)} />
+        </Show>
         <ServerGate>
           {(data) => {
-            setServerPassword(data().password)
+            const auth = data().auth
+            setServerAuth(auth)
             window.__OPENCODE__ ??= {}
-            window.__OPENCODE__.serverPassword = data().password ?? undefined
+            window.__OPENCODE__.serverUsername = auth?.type === "basic" ? auth.username : undefined
+            window.__OPENCODE__.serverPassword = auth?.type === "basic" ? auth.password : undefined
+            window.__OPENCODE__.serverAuthorization = auth ? authorizationHeader(auth) : undefined
 
             function Inner() {
               const cmd = useCommand()
 
               menuTrigger = (id) => cmd.trigger(id)
 
+              cmd.register("desktop", () => [
+                {
+                  id: "server.attach",
+                  title: t("desktop.attachServer.command"),
+                  category: t("command.category.server"),
+                  onSelect: () => setAttaching(true),
+                },
+              ])
+
               return null
             }
 
@@ -446,26 +472,35 @@ render(() => {
   )
 }, root!)
 
-type ServerReadyData = { url: string; password: string | null }
+type ServerReadyData = { url: string; auth: ServerAuth | null }
 
 // Gate component that waits for the server to be ready
 function ServerGate(props: { children: (data: Accessor<ServerReadyData>) => JSX.Element }) {
   const [serverData] = createResource(() => commands.awaitInitialization(new Channel<InitStep>() as any))
 
-  if (serverData.state === "errored") throw serverData.error
+  // Startup failures get their own screen, anything else goes to the error boundary
+  const startupError = () =>
+    serverData.state === "errored" && isStartupError(serverData.error) ? serverData.error : undefined
 
   return (
-    // Not using suspense as not all components are compatible with it (undefined refs)
     <Show
-      when={serverData.state !== "pending" && serverData()}
+      when={startupError()}
       fallback={
-        <div class="h-screen w-screen flex flex-col items-center justify-center bg-background-base">
-          <Splash class="w-16 h-20 opacity-50 animate-pulse" />
-          <div data-tauri-decorum-tb class="flex flex-row absolute top-0 right-0 z-10 h-10" />
-        </div>
+        // Not using suspense as not all components are compatible with it (undefined refs)
+        <Show
+          when={serverData.state !== "pending" && serverData()}
+          fallback={
+            <div class="h-screen w-screen flex flex-col items-center justify-center bg-background-base">
+              <Splash class="w-16 h-20 opacity-50 animate-pulse" />
+              <div data-tauri-decorum-tb class="flex flex-row absolute top-0 right-0 z-10 h-10" />
+            </div>
+          }
+        >
+          {(data) => props.children(data)}
+        </Show>
       }
     >
-      {(data) => props.children(data)}
+      {(error) => <StartupErrorScreen error={error()} />}
     </Show>
   )
 }
@@ -5,15 +5,40 @@ import { Font } from "@opencode-ai/ui/font"
 import { Splash } from "@opencode-ai/ui/logo"
 import "./styles.css"
 import { createSignal, Match, onMount } from "solid-js"
-import { commands, events, InitStep } from "./bindings"
+import { commands, events, InitPhase, InitStep } from "./bindings"
 import { Channel } from "@tauri-apps/api/core"
 import { Switch } from "solid-js"
 
 const root = document.getElementById("root")!
 
+// Phases that take longer than this show how long they've been running
+const SLOW_PHASE_MS = 3000
+
+function describe(phase: InitPhase) {
+  switch (phase.type) {
+    case "resolving_config":
+      return "Loading configuration"
+    case "connecting_remote":
+      return `Connecting to ${phase.url}`
+    case "resolving_shell_env":
+      return "Loading your shell environment"
+    case "spawning":
+      return "Starting the server"
+    case "health_checking":
+      return "Waiting for the server"
+    default:
+      return "Just a moment..."
+  }
+}
+
 render(() => {
   let splash!: SVGSVGElement
   const [state, setState] = createSignal<InitStep | null>(null)
+  const slow = () => (state()?.phaseElapsedMs ?? 0) >= SLOW_PHASE_MS
+  const migration = () => {
+    const phase = state()?.phase
+    return phase?.type === "migrating" ? phase : undefined
+  }
 
   const channel = new Channel<InitStep>()
   channel.onmessage = (e) => setState(e)
@@ -40,7 +65,7 @@ render(() => {
           <Splash ref={splash} class="h-25 animate-[pulse-splash_2s_ease-in-out_infinite]" />
           <span class="text-text-base">
             <Switch fallback="Just a moment...">
-              <Match when={state()?.phase === "done"}>
+              <Match when={state()?.phase.type === "done"}>
                 {(_) => {
                   onMount(() => {
                     setTimeout(() => events.loadingWindowComplete.emit(null), 1000)
@@ -49,24 +74,24 @@ render(() => {
                   return "All done"
                 }}
               </Match>
-              <Match when={state()?.phase === "sqlite_waiting"}>
-                {(_) => {
-                  const textItems = [
-                    "Just a moment...",
-                    "Migrating your database",
-                    "This could take a couple of minutes",
-                  ]
-                  const [textIndex, setTextIndex] = createSignal(0)
-
-                  onMount(async () => {
-                    await new Promise((res) => setTimeout(res, 3000))
-                    setTextIndex(1)
-                    await new Promise((res) => setTimeout(res, 6000))
-                    setTextIndex(2)
-                  })
-
-                  return <>{textItems[textIndex()]}</>
-                }}
+              <Match when={migration()}>
+                {(phase) => (
+                  <>
+                    Migrating your database
+                    {phase().current !== null && phase().total !== null
+                      ? ` (${phase().current} of ${phase().total})`
+                      : ""}
+                    {slow() ? `, this could take a couple of minutes` : ""}
+                  </>
+                )}
+              </Match>
+              <Match when={state()}>
+                {(step) => (
+                  <>
+                    {describe(step().phase)}
+                    {slow() ? ` (${Math.round(step().phaseElapsedMs / 1000)}s)` : ""}
+                  </>
+                )}
               </Match>
             </Switch>
           </span>
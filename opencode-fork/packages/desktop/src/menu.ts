@@ -41,6 +41,10 @@ export async function createMenu(trigger: (id: string) => void) {
             },
             text: t("desktop.menu.restart"),
           }),
+          await MenuItem.new({
+            action: () => trigger("server.attach"),
+            text: t("desktop.attachServer.command"),
+          }),
           await PredefinedMenuItem.new({
             item: "Separator",
           }),
@@ -0,0 +1,87 @@
+import { Button } from "@opencode-ai/ui/button"
+import { Logo } from "@opencode-ai/ui/logo"
+import { TextField } from "@opencode-ai/ui/text-field"
+import { openPath } from "@tauri-apps/plugin-opener"
+import { relaunch } from "@tauri-apps/plugin-process"
+import { For, Show } from "solid-js"
+
+import { commands, Remedy, StartupError } from "./bindings"
+import { t } from "./i18n"
+
+export function isStartupError(error: unknown): error is StartupError {
+  return typeof error === "object" && error !== null && "reason" in error && "remedies" in error
+}
+
+// Remedies the screen can carry out itself, the rest are only described
+const actions: Partial<Record<Remedy, (error: StartupError) => Promise<unknown>>> = {
+  retry: () => relaunch(),
+  use_automatic_port: async () => {
+    await commands.setSidecarPortSetting({ mode: "auto" })
+    await relaunch()
+  },
+  reset_launch_options: async () => {
+    const options = await commands.getSidecarLaunchOptions()
+    await commands.setSidecarLaunchOptions({ ...options, env: {}, args: [] })
+    await relaunch()
+  },
+  open_logs: async (error) => {
+    if (error.logPath) await openPath(error.logPath)
+  },
+}
+
+export function StartupErrorScreen(props: { error: StartupError }) {
+  const hints = () => props.error.remedies.filter((remedy) => !actions[remedy])
+  const buttons = () =>
+    props.error.remedies.filter((remedy) => actions[remedy] && (remedy !== "open_logs" || props.error.logPath))
+
+  return (
+    <div class="relative flex-1 h-screen w-screen min-h-0 flex flex-col items-center justify-center bg-background-base font-sans">
+      <div data-tauri-decorum-tb class="flex flex-row absolute top-0 right-0 z-10 h-10" />
+      <div class="w-2/3 max-w-3xl flex flex-col items-center justify-center gap-8">
+        <Logo class="w-58.5 opacity-12 shrink-0" />
+        <div class="flex flex-col items-center gap-2 text-center">
+          <h1 class="text-lg font-medium text-text-strong">{t("desktop.startupError.title")}</h1>
+          <p class="text-sm text-text-weak whitespace-pre-wrap">{props.error.message}</p>
+        </div>
+        <Show when={hints().length > 0}>
+          <ul class="flex flex-col gap-1 text-sm text-text-base list-disc">
+            <For each={hints()}>{(remedy) => <li>{t(`desktop.startupError.remedy.${remedy}`)}</li>}</For>
+          </ul>
+        </Show>
+        <Show when={props.error.stderr.length > 0}>
+          <TextField
+            value={props.error.stderr.join("\n")}
+            readOnly
+            copyable
+            multiline
+            class="max-h-64 w-full font-mono text-xs no-scrollbar"
+            label={t("desktop.startupError.output")}
+          />
+        </Show>
+        <Show when={props.error.log.length > 0}>
+          <TextField
+            value={props.error.log.join("\n")}
+            readOnly
+            copyable
+            multiline
+            class="max-h-64 w-full font-mono text-xs no-scrollbar"
+            label={t("desktop.startupError.log")}
+          />
+        </Show>
+        <div class="flex items-center gap-3">
+          <For each={buttons()}>
+            {(remedy, index) => (
+              <Button
+                size="large"
+                variant={index() === 0 ? undefined : "ghost"}
+                onClick={() => actions[remedy]?.(props.error)}
+              >
+                {t(`desktop.startupError.action.${remedy}`)}
+              </Button>
+            )}
+          </For>
+        </div>
+      </div>
+    </div>
+  )
+}
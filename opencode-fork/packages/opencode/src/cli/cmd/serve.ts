@@ -5,15 +5,20 @@ import { Flag } from "../../flag/flag"
 
 export const ServeCommand = cmd({
   command: "serve",
-  builder: (yargs) => withNetworkOptions(yargs),
+  builder: (yargs) =>
+    withNetworkOptions(yargs).option("unix-socket", {
+      type: "string",
+      describe: "listen on a unix socket instead of a TCP port",
+    }),
   describe: "starts a headless opencode server",
   handler: async (args) => {
     if (!Flag.OPENCODE_SERVER_PASSWORD) {
       console.log("Warning: OPENCODE_SERVER_PASSWORD is not set; server is unsecured.")
     }
     const opts = await resolveNetworkOptions(args)
-    const server = Server.listen(opts)
-    console.log(`opencode server listening on http://${server.hostname}:${server.port}`)
+    const server = Server.listen({ ...opts, unixSocket: args["unix-socket"] })
+    if (args["unix-socket"]) console.log(`opencode server listening on unix:${args["unix-socket"]}`)
+    else console.log(`opencode server listening on http://${server.hostname}:${server.port}`)
     await new Promise(() => {})
     await server.stop()
   },
@@ -0,0 +1,51 @@
+import fs from "fs"
+import path from "path"
+import { Log } from "@/util/log"
+import { Global } from "../global"
+import { Installation } from "../installation"
+
+const log = Log.create({ service: "discovery" })
+
+// Lets local clients such as the desktop app find running servers without scanning ports
+export namespace Discovery {
+  const dir = path.join(Global.Path.state, "servers")
+  const file = path.join(dir, `${process.pid}.json`)
+  // "exit" doesn't run for these, the desktop app removes files of servers that were killed
+  const signals = ["SIGINT", "SIGTERM", "SIGHUP"] as const
+
+  function onSignal(signal: NodeJS.Signals) {
+    unpublish()
+    // Listening replaces the default of exiting, so exit like it would unless someone else handles it
+    if (process.listenerCount(signal) === 0) process.kill(process.pid, signal)
+  }
+
+  export function publish(input: { url: URL; hostname: string; port: number }) {
+    try {
+      fs.mkdirSync(dir, { recursive: true })
+      fs.writeFileSync(
+        file,
+        JSON.stringify({
+          pid: process.pid,
+          url: input.url.toString(),
+          hostname: input.hostname,
+          port: input.port,
+          version: Installation.VERSION,
+          directory: process.cwd(),
+          auth: !!process.env["OPENCODE_SERVER_PASSWORD"],
+          startedAt: Date.now(),
+        }),
+      )
+      process.once("exit", unpublish)
+      for (const signal of signals) process.once(signal, onSignal)
+      log.info("published", { file })
+    } catch (err) {
+      log.error("publish failed", { error: err })
+    }
+  }
+
+  export function unpublish() {
+    try {
+      fs.rmSync(file, { force: true })
+    } catch {}
+  }
+}
@@ -40,6 +40,8 @@ import { QuestionRoutes } from "./routes/question"
 import { PermissionRoutes } from "./routes/permission"
 import { GlobalRoutes } from "./routes/global"
 import { MDNS } from "./mdns"
+import { Discovery } from "./discovery"
+import fs from "fs"
 
 // @ts-ignore This global is needed to prevent ai-sdk from logging warnings to stdout https://github.com/vercel/ai/blob/2dc67e0ef538307f21368db32d5a12345d98831b/packages/ai/src/logger/log-warnings.ts#L85
 globalThis.AI_SDK_LOG_WARNINGS = false
@@ -574,12 +576,15 @@ export namespace Server {
   export function listen(opts: {
     port: number
     hostname: string
+    unixSocket?: string
     mdns?: boolean
     mdnsDomain?: string
     cors?: string[]
   }) {
     _corsWhitelist = opts.cors ?? []
 
+    if (opts.unixSocket) return listenUnix(opts.unixSocket)
+
     const args = {
       hostname: opts.hostname,
       idleTimeout: 0,
@@ -610,12 +615,39 @@ export namespace Server {
       log.warn("mDNS enabled but hostname is loopback; skipping mDNS publish")
     }
 
+    Discovery.publish({ url: server.url, hostname: opts.hostname, port: server.port! })
+
     const originalStop = server.stop.bind(server)
     server.stop = async (closeActiveConnections?: boolean) => {
       if (shouldPublishMDNS) MDNS.unpublish()
+      Discovery.unpublish()
       return originalStop(closeActiveConnections)
     }
 
     return server
   }
+
+  // Private to the user that owns the socket, so it isn't published for discovery
+  function listenUnix(socket: string) {
+    // A socket file left behind by a crashed server would make the bind fail
+    fs.rmSync(socket, { force: true })
+
+    const server = Bun.serve({
+      unix: socket,
+      idleTimeout: 0,
+      fetch: App().fetch,
+      websocket: websocket,
+    })
+    fs.chmodSync(socket, 0o600)
+
+    _url = server.url
+
+    const originalStop = server.stop.bind(server)
+    server.stop = async (closeActiveConnections?: boolean) => {
+      await originalStop(closeActiveConnections)
+      fs.rmSync(socket, { force: true })
+    }
+
+    return server
+  }
 }
@@ -2,6 +2,7 @@ import { Log } from "../util/log"
 import path from "path"
 import fs from "fs/promises"
 import { Global } from "../global"
+import { Flag } from "../flag/flag"
 import { Filesystem } from "../util/filesystem"
 import { lazy } from "../util/lazy"
 import { Lock } from "../util/lock"
@@ -148,7 +149,10 @@ export namespace Storage {
       .then((x) => parseInt(x))
       .catch(() => 0)
     for (let index = migration; index < MIGRATIONS.length; index++) {
-      log.info("running migration", { index })
+      log.info("running migration", { index, total: MIGRATIONS.length })
+      // The desktop app runs its server at WARN, so progress gets a line of its own to show while it waits
+      if (Flag.OPENCODE_CLIENT === "desktop")
+        process.stderr.write(`opencode-migration index=${index} total=${MIGRATIONS.length}\n`)
       const migration = MIGRATIONS[index]
       await migration(dir).catch(() => log.error("failed to run migration", { index }))
       await Bun.write(path.join(dir, "migration"), (index + 1).toString())